@@ -1,8 +1,8 @@
 use ragout::console::raw_mode::{cooked_mode, raw_mode};
 use ragout::console::winsize::winsize;
-use ragout::inputs::keyboard::{Char, KbdEvent, Modifiers};
-use ragout::inputs::mouse::{disable_mouse_input, enable_mouse_input};
-use ragout::inputs::{event, read, Interaction};
+use ragout::inputs::mouse::{disable_mouse_input, enable_mouse_input, Tracking};
+use ragout::inputs::window::resize;
+use ragout::inputs::{event, read, read_timeout, Interaction, Keymap, Resolved};
 
 use std::io::Write;
 
@@ -11,32 +11,55 @@ fn main() {
     let ts = raw_mode();
 
     let mut writer = std::io::stdout().lock();
-    enable_mouse_input(&mut writer);
+    enable_mouse_input(&mut writer, Tracking::Motion);
     _ = writer.write(b"\x1b[?1049h\x1b[0;0f");
     _ = writer.flush();
 
     let mut ws = winsize::from_ioctl();
+    resize::listen();
 
     let mut reader = std::io::stdin().lock();
 
     let mut i = vec![];
 
-    loop {
-        let input = read(&mut reader, &mut i);
-        print!("{:?}\r\n", input);
+    let mut keymap = Keymap::new();
+    _ = keymap.bind("C-c", "quit");
+    _ = keymap.bind("C-x C-s", "save");
 
-        let ui = event(input, &mut ws);
-        print!("{:?}\r\n{:?}\r\n\r\n", &ui.event, &ui.time);
+    'main: loop {
+        // a started chord only waits for its next key until the chord timeout
+        let input = match keymap.timeout() {
+            Some(timeout) => read_timeout(&mut reader, &mut i, timeout),
+            None => Some(read(&mut reader, &mut i)),
+        };
 
-        // print!("{:?}\r\n", &ui);
-        _ = writer.flush();
+        if let Some(input) = input {
+            print!("{:?}\r\n", input);
 
-        if let Interaction::KbdEvent(KbdEvent {
-            char: Char::Char('c'),
-            modifiers: Modifiers(2),
-        }) = ui.event
-        {
-            break;
+            let ui = event(input, &mut ws);
+            print!("{:?}\r\n{:?}\r\n\r\n", &ui.event, &ui.time);
+
+            // print!("{:?}\r\n", &ui);
+            _ = writer.flush();
+
+            match ui.event {
+                Interaction::KbdEvent(ke) => match keymap.feed(ke) {
+                    Resolved::Action(&"quit") => break,
+                    Resolved::Action(action) => print!("{}\r\n", action),
+                    _ => (),
+                },
+                Interaction::Closed => break,
+                _ => (),
+            }
+        }
+
+        // the keys that broke a chord and the chords that timed out
+        while let Some(resolved) = keymap.flush() {
+            match resolved {
+                Resolved::Action(&"quit") => break 'main,
+                Resolved::Action(action) => print!("{}\r\n", action),
+                _ => (),
+            }
         }
     }
 
@@ -1,3 +1,5 @@
+use crate::components::term::inline_region;
+use crate::console::title::set_title;
 use crate::console::winsize::winsize;
 use crate::render_pipeline;
 use crate::space::{area_conflicts, between, border::Border, border_fit, padding::Padding};
@@ -17,7 +19,7 @@ pub mod text;
 pub use builders::{ContainerMeta, InputMeta, NonEditMeta, TermMeta};
 pub use container::Container;
 pub use term::Term;
-pub use text::Text;
+pub use text::{Hyperlink, Text};
 
 type TermTree = Vec<u8>;
 type ContainerTree = Vec<[u8; 2]>;
@@ -544,6 +546,7 @@ impl ComponentTree {
 
     /// changes the active Term of this tree
     /// the active term is the term that gets rendered
+    /// the new active term's title, if it declares one, gets applied to the terminal window
     ///
     /// # Errors
     ///
@@ -552,12 +555,36 @@ impl ComponentTree {
         if self.has_term(id) {
             self.term = id;
 
+            if let Some(title) = self.term_ref(id).and_then(|t| t.title.as_deref()) {
+                let mut writer = std::io::stdout().lock();
+                set_title(&mut writer, title);
+                _ = writer.flush();
+            }
+
             return Ok(());
         }
 
         Err(ComponentTreeError::BadID)
     }
 
+    /// clears the terminal display and renders the active Term in full, in a single frame
+    /// e.g., on a WindowEvent::Resumed, when the screen content was lost during the suspension
+    pub fn redraw(&mut self, writer: &mut StdoutLock) {
+        let id = self.term;
+        let Some(term) = self.term_mut(id) else {
+            return;
+        };
+
+        let mut frame = String::new();
+        // the suspension reset the inline viewport's scroll region
+        if let Some(origin) = term.inline {
+            frame.push_str(&inline_region(origin, term.h));
+        }
+        frame.push_str("\x1b[H\x1b[J");
+        frame.push_str(&term.frame());
+        term.write_frame(writer, frame.as_bytes());
+    }
+
     /// takes no id and automatically assigns an id while adding a new Term
     /// returns the new term id
     pub fn term_auto(&mut self) -> u8 {
@@ -884,4 +911,157 @@ mod test_term {
 mod test_container {}
 
 #[cfg(test)]
-mod test_text {}
+mod test_inline {
+    use super::term::{inline_enter, inline_origin, inline_region, inline_resize};
+    use super::Term;
+    use crate::console::session::SERIAL;
+    use crate::inputs::Replay;
+    use std::time::Duration;
+
+    #[test]
+    fn origin() {
+        // fits under the cursor
+        assert_eq!(inline_origin(3, 4, 24), 3);
+        assert_eq!(inline_origin(21, 4, 24), 21);
+        // the screen scrolls up
+        assert_eq!(inline_origin(22, 4, 24), 21);
+        assert_eq!(inline_origin(24, 1, 24), 24);
+        assert_eq!(inline_origin(24, 24, 24), 1);
+    }
+
+    #[test]
+    fn sequences() {
+        // the scroll region covers the viewport, origin mode makes its first line row 1
+        assert_eq!(inline_region(21, 4), "\x1b[21;24r\x1b[?6h");
+        assert_eq!(inline_region(5, 1), "\x1b[5;5r\x1b[?6h");
+
+        // always h - 1 line feeds, they only scroll when the cursor is at the bottom
+        assert_eq!(inline_enter(3, 4, 24), (3, "\n\n\n\x1b[3;6r\x1b[?6h".to_string()));
+        assert_eq!(inline_enter(24, 3, 24), (22, "\n\n\x1b[22;24r\x1b[?6h".to_string()));
+    }
+
+    #[test]
+    fn resize() {
+        // shrinking clears the lines left behind
+        assert_eq!(
+            inline_resize(5, 3, 1, 24),
+            (5, "\x1b[?6l\x1b[r\x1b[6;1f\x1b[2K\x1b[7;1f\x1b[2K\x1b[5;5r\x1b[?6h".to_string())
+        );
+
+        // growing with room left under the viewport
+        assert_eq!(
+            inline_resize(5, 1, 3, 24),
+            (5, "\x1b[?6l\x1b[r\x1b[5;7r\x1b[?6h".to_string())
+        );
+
+        // growing at the bottom edge scrolls the screen up by the missing lines
+        assert_eq!(
+            inline_resize(22, 3, 5, 24),
+            (20, "\x1b[?6l\x1b[r\x1b[24;1f\n\n\x1b[20;24r\x1b[?6h".to_string())
+        );
+
+        // a viewport as high as the screen starts at its top
+        assert_eq!(
+            inline_resize(10, 2, 24, 24),
+            (1, format!("\x1b[?6l\x1b[r\x1b[24;1f{}\x1b[1;24r\x1b[?6h", "\n".repeat(9)))
+        );
+    }
+
+    #[test]
+    fn written() {
+        // these mark the scroll region of a session a test may have started
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+
+        // a terminal that never answers the cursor position request
+        let mut reader = Replay::new("".as_bytes()).unwrap();
+        let mut bytes = vec![];
+        let mut term = Term::inline(0, 1, &mut reader, &mut bytes, Duration::from_millis(1));
+        assert!(bytes.starts_with(b"\x1b[6n"));
+        assert!(bytes.ends_with(b"\x1b[?6h"));
+        assert!(term.inline.is_some());
+
+        bytes.clear();
+        term.resize_inline(&mut bytes, 1);
+        assert!(bytes.ends_with(b"\x1b[?6h"));
+
+        bytes.clear();
+        term.leave_inline(&mut bytes);
+        assert!(bytes.starts_with(b"\x1b[?6l\x1b[r"));
+    }
+}
+
+#[cfg(test)]
+mod test_text {
+    use super::{Container, Term, Text};
+    use crate::space::{border::Border, padding::Padding};
+
+    #[test]
+    fn links() {
+        let mut text = Text::new([0, 0, 1], 0, 0, 1, 1, 4, 2, &[], Border::None, Padding::None);
+        text.value = "abcdefgh".chars().map(Some).collect();
+        text.link(2, 6, "https://example.com", None);
+        text.link(7, 8, "file:///etc/hosts", Some("hosts"));
+        assert_eq!(text.link_at(5).unwrap().0, 0);
+        assert!(text.link_at(6).is_none());
+
+        let mut out = vec![];
+        text.render_value(&mut out);
+        let out = String::from_utf8(out).unwrap();
+
+        // the link is cut at the line end and goes on with the same id on the next line
+        let open = "\x1b]8;id=ragout-0-0-1-0;https://example.com\x1b\\";
+        let close = "\x1b]8;;\x1b\\";
+        assert!(out.contains(&format!("ab{}cd{}", open, close)));
+        assert!(out.contains(&format!("{}ef{}g", open, close)));
+        assert!(out.contains(&format!("\x1b]8;id=hosts;file:///etc/hosts\x1b\\h{}", close)));
+    }
+    #[test]
+    fn term_links() {
+        let mut text = Text::new([0, 0, 1], 0, 0, 1, 1, 4, 2, &[], Border::None, Padding::None);
+        text.value = "abcdefgh".chars().map(Some).collect();
+        text.link(2, 6, "https://example.com", None);
+        let mut container = Container::new([0, 0], 0, 0, 4, 2, Border::None, Padding::None);
+        container.items.push(text);
+        let mut term = Term::new(0, 4, 2);
+        term.containers.push(container);
+
+        // closed before the line break, opened again after it with the same id
+        // a text without a border gets no value drawn into the term buffer, its cells are skipped
+        let open = "\x1b]8;id=ragout-0-0-1-0;https://example.com\x1b\\";
+        let close = "\x1b]8;;\x1b\\";
+        let skip = "\x1b[C\x1b[C";
+        assert!(term.frame().starts_with(&format!(
+            "{skip}{open}{skip}{close}\r\n{open}{skip}{close}{skip}\x1b[0;0f"
+        )));
+    }
+    #[test]
+    fn graphemes() {
+        let mut text = Text::new([0, 0, 1], 0, 0, 1, 1, 4, 2, &[], Border::None, Padding::None);
+        text.value = vec![None; 8];
+        let family = "👨\u{200d}👩\u{200d}👧";
+
+        // lam alef takes 1 cell, the family emoji 2 cells
+        assert!(text.insert_grapheme("لا"));
+        assert_eq!([text.cx, text.cy], [1, 0]);
+        assert!(text.insert_grapheme(family));
+        assert_eq!([text.cx, text.cy], [3, 0]);
+        // no room left on the line, it goes on the next one
+        assert!(text.insert_grapheme(family));
+        assert_eq!([text.cx, text.cy], [2, 1]);
+
+        assert_eq!(text.grapheme_at(0), Some(("لا", 1)));
+        assert_eq!(text.grapheme_at(1), Some((family, 2)));
+        assert_eq!(text.grapheme_at(2), None);
+        assert_eq!(text.grapheme_at(4), Some((family, 2)));
+
+        // a char typed over the cluster replaces it
+        text.value[4] = Some('x');
+        assert_eq!(text.grapheme_at(4), None);
+
+        let mut out = vec![];
+        text.render_value(&mut out);
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains(&format!("لا{family}\x1b[C")));
+        assert!(out.contains("x\x1b[C\x1b[C\x1b[C"));
+    }
+}
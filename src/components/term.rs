@@ -1,12 +1,16 @@
 use std::collections::{HashMap, HashSet};
-use std::io::StdoutLock;
 use std::io::Write;
+use std::time::Duration;
 
+use crate::console::capabilities::Capabilities;
+use crate::console::cursor::{cursor_position, CursorStyle};
+use crate::console::session::mark_region;
 use crate::console::winsize::winsize;
 use crate::render_pipeline;
 use crate::space::{
-    area_conflicts, between,  border_fit, calc_text_abs_ori, resolve_wh, Area, border::Border, padding::Padding, Pos,
+    area_conflicts, between,  border_fit, calc_text_abs_ori, resolve_wh, Area, border::Border, padding::Padding, Polygon, Pos,
 };
+use crate::inputs::InputSource;
 use crate::layout::Layout;
 use crate::themes::Style;
 
@@ -30,6 +34,18 @@ pub struct Term {
     pub cx: u16,
     /// the current terminal cursor y coordinate
     pub cy: u16,
+    /// the cursor shape and visibility that render applies
+    /// the focused Text's own cursor style takes precedence over it
+    pub cursor: CursorStyle,
+    /// whether renders get wrapped in synchronized updates (mode 2026)
+    /// off by default, nothing probes the terminal for it on its own;
+    /// set it from a console::capabilities probe with Term::use_capabilities
+    pub synchronized_output: bool,
+    /// the terminal window title to set when this Term gets focused in its ComponentTree
+    pub title: Option<String>,
+    /// the screen row of the first line of an inline Term, None for a full screen Term
+    /// see Term::inline
+    pub inline: Option<u16>,
     /// a vector of all the Containers inside this Term
     pub containers: Vec<Container>,
     // pub border: Border,
@@ -188,6 +204,171 @@ impl Term {
     }
 }
 
+impl Term {
+    /// sets the window title that this Term declares
+    /// it is applied every time ComponentTree::focus switches to this Term
+    pub fn title(&mut self, title: &str) {
+        self.title = Some(title.to_string());
+    }
+
+    /// turns on the output features the probed terminal supports
+    /// i.e., synchronized_output
+    ///
+    /// # Examples
+    /// ```
+    /// let caps = capabilities(&mut reader, &mut writer, Duration::from_millis(100));
+    /// term.use_capabilities(&caps);
+    /// ```
+    pub fn use_capabilities(&mut self, caps: &Capabilities) {
+        self.synchronized_output = caps.synchronized_output();
+    }
+
+    /// returns the cursor style that render applies
+    /// the focused Text's cursor style if it has one, otherwise this Term's
+    pub fn cursor_style(&self) -> CursorStyle {
+        let text = match self.focused {
+            Some(id) if id[2] % 2 == 0 => self.input_ref(&id),
+            Some(id) => self.nonedit_ref(&id),
+            None => None,
+        };
+
+        text.and_then(|t| t.cursor).unwrap_or(self.cursor)
+    }
+}
+
+impl Term {
+    /// returns a new inline Term, drawn in the normal screen from the cursor row down
+    /// instead of taking the whole alternate screen; e.g., an installer's progress lines
+    ///
+    /// asks the terminal for the cursor position, then scrolls the screen up if the h lines
+    /// don't fit under the cursor.
+    /// the viewport is made a scroll region in origin mode, so the render methods'
+    /// positions are relative to the first line of the inline Term
+    ///
+    /// the terminal has to be in raw mode for the cursor position reply to arrive;
+    /// without a reply the Term goes at the bottom of the screen
+    ///
+    /// # Examples
+    /// ```
+    /// let ts = raw_mode();
+    /// let mut term = Term::inline(0, 3, &mut reader, &mut writer, Duration::from_millis(100));
+    /// // render, resize_inline as the progress grows
+    /// term.leave_inline(&mut writer);
+    /// ```
+    pub fn inline<S: InputSource>(
+        id: u8,
+        h: u16,
+        reader: &mut S,
+        writer: &mut impl Write,
+        timeout: Duration,
+    ) -> Self {
+        let ws = winsize::from_ioctl();
+        let rows = ws.rows().max(1);
+        let h = h.clamp(1, rows);
+
+        let mut s = String::new();
+        let row = match cursor_position(reader, writer, timeout) {
+            Some([1, row]) => row,
+            // start on a line of its own
+            Some([_, row]) => {
+                s.push_str("\r\n");
+                row + 1
+            }
+            None => rows,
+        };
+        let row = row.min(rows);
+
+        let (origin, enter) = inline_enter(row, h, rows);
+        s.push_str(&enter);
+        _ = writer.write_all(s.as_bytes());
+        _ = writer.flush();
+        mark_region(true);
+
+        Term {
+            id,
+            w: ws.cols(),
+            h,
+            inline: Some(origin),
+            ..Default::default()
+        }
+    }
+
+    /// grows or shrinks an inline Term to h lines
+    /// the lines it no longer uses get cleared, the screen scrolls up when the new lines don't fit
+    /// does nothing on a full screen Term
+    pub fn resize_inline(&mut self, writer: &mut impl Write, h: u16) {
+        let Some(origin) = self.inline else {
+            return;
+        };
+        let rows = winsize::from_ioctl().rows().max(1);
+        let h = h.clamp(1, rows);
+
+        let (new, s) = inline_resize(origin, self.h, h, rows);
+        _ = writer.write_all(s.as_bytes());
+        _ = writer.flush();
+        mark_region(true);
+
+        self.inline = Some(new);
+        self.h = h;
+    }
+
+    /// ends the inline mode and leaves the cursor on the line under the Term
+    /// whatever was rendered stays in the scrollback
+    /// does nothing on a full screen Term
+    pub fn leave_inline(&self, writer: &mut impl Write) {
+        let Some(origin) = self.inline else {
+            return;
+        };
+
+        let s = format!("\x1b[?6l\x1b[r\x1b[{};1f\r\n", origin + self.h - 1);
+        _ = writer.write(s.as_bytes());
+        _ = writer.flush();
+        mark_region(false);
+    }
+}
+
+// the origin of a new inline viewport of h lines at the given row, and the sequences that set it up
+// line feeds at the bottom of the screen scroll it up to make room
+pub(crate) fn inline_enter(row: u16, h: u16, rows: u16) -> (u16, String) {
+    let origin = inline_origin(row, h, rows);
+    let mut s = "\n".repeat((h - 1) as usize);
+    s.push_str(&inline_region(origin, h));
+
+    (origin, s)
+}
+
+// the new origin of an inline viewport going from h to new_h lines, and the sequences that move it
+pub(crate) fn inline_resize(origin: u16, h: u16, new_h: u16, rows: u16) -> (u16, String) {
+    // out of origin mode, to reach the lines outside the viewport
+    let mut s = String::from("\x1b[?6l\x1b[r");
+    for row in origin + new_h..origin + h {
+        s.push_str(&format!("\x1b[{};1f\x1b[2K", row));
+    }
+
+    let new = inline_origin(origin, new_h, rows);
+    if new < origin {
+        s.push_str(&format!("\x1b[{};1f", rows));
+        s.push_str(&"\n".repeat((origin - new) as usize));
+    }
+    s.push_str(&inline_region(new, new_h));
+
+    (new, s)
+}
+
+// the first row of an inline viewport of h lines that wants to start at the given row
+// moved up when the screen is too short under it
+pub(crate) fn inline_origin(row: u16, h: u16, rows: u16) -> u16 {
+    match row + h - 1 > rows {
+        true => rows.saturating_sub(h) + 1,
+        false => row,
+    }
+}
+
+// confines the cursor and the scrolling to the viewport, with positions relative to its first row
+pub(crate) fn inline_region(origin: u16, h: u16) -> String {
+    format!("\x1b[{};{}r\x1b[?6h", origin, origin + h - 1)
+}
+
 impl Term {
     /// adds a new Container object to this Term's containers
     ///
@@ -1,8 +1,10 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::io::StdoutLock;
 use std::io::Write;
 
+use crate::console::cursor::CursorStyle;
 use crate::console::winsize::winsize;
+use crate::inputs::keyboard::grapheme::width;
 use crate::render_pipeline;
 use crate::space::{area_conflicts, between, border::Border, border_fit, padding::Padding};
 use crate::themes::Style;
@@ -24,8 +26,13 @@ pub struct Text {
     /// temporary value holder for use when scorrling history
     // this should be part of properties
     pub temp: Vec<Option<char>>,
-    /// the value inside this Text object
+    /// the value inside this Text object, one char per cell
+    /// see graphemes for the cells that hold a whole grapheme cluster
     pub value: Vec<Option<char>>,
+    /// the grapheme clusters of the value, e.g., from a Char::Grapheme, by the index of their cell
+    /// the cell holds the first char of its cluster, the cluster is rendered in its place
+    /// as long as the cell keeps that char; a 2 columns wide cluster also covers the next cell
+    pub graphemes: BTreeMap<usize, String>,
     /// history cursor current value
     // this field should be part of properties
     pub hicu: usize,
@@ -37,6 +44,9 @@ pub struct Text {
     pub cx: u16,
     /// this Text's cursor y coordinate
     pub cy: u16,
+    /// the cursor shape and visibility to use while this Text is focused
+    /// None falls back to the parent Term's cursor style
+    pub cursor: Option<CursorStyle>,
     /// origin point x coordinate relative to the dimensions of the parent Container
     pub x0: u16,
     /// origin point y coordinate relative to the dimensions of the parent Container
@@ -55,10 +65,55 @@ pub struct Text {
     /// value style
     pub vstyle: String,
 
+    /// hyperlink spans over the value cells, rendered with OSC 8
+    pub links: Vec<Hyperlink>,
+
     pub properties: HashMap<&'static str, Property>,
     pub attributes: HashSet<&'static str>,
 }
 
+/// a range of value cells that links to a uri
+/// the range goes over the value line by line, a span that passes a line's end continues on the next line
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hyperlink {
+    /// the index of the first cell of the span in the Text value
+    pub start: usize,
+    /// the index right after the last cell of the span
+    pub end: usize,
+    /// the link target, e.g., https://example.com or file:///etc/hosts
+    pub uri: String,
+    /// cells with the same id and uri make one link for the terminal, e.g., when hovered
+    /// None gets an id generated from the Text id so that the span stays one link over many lines
+    pub id: Option<String>,
+}
+
+impl Hyperlink {
+    /// returns the OSC 8 sequence that starts this link
+    /// fallback is the id used when the link has none
+    pub fn open(&self, fallback: &str) -> String {
+        // the id is a ':' separated parameter and the sequence ends at a control character
+        let clean = |s: &str, extra: &[char]| {
+            s.chars()
+                .filter(|c| !c.is_control() && !extra.contains(c))
+                .collect::<String>()
+        };
+
+        format!(
+            "\x1b]8;id={};{}\x1b\\",
+            clean(self.id.as_deref().unwrap_or(fallback), &[':', ';']),
+            clean(&self.uri, &[])
+        )
+    }
+
+    /// the OSC 8 sequence that ends any open link
+    pub const CLOSE: &'static str = "\x1b]8;;\x1b\\";
+
+    /// whether the given value cell is part of this link
+    pub fn contains(&self, cell: usize) -> bool {
+        (self.start..self.end).contains(&cell)
+    }
+}
+
 // NOTE: Inputs can only have pair IDs
 // while NonEdits can only have odd IDs
 impl Text {
@@ -99,6 +154,9 @@ impl Text {
             },
             cx: 0,
             cy: 0,
+            cursor: None,
+            links: vec![],
+            graphemes: BTreeMap::new(),
 
             layer: 0,
             vstyle: "".to_string(),
@@ -111,6 +169,93 @@ impl Text {
         self.vstyle = style.style();
     }
 
+    /// changes the cursor style used while this text is focused
+    pub fn cursor(&mut self, cursor: CursorStyle) {
+        self.cursor = Some(cursor);
+    }
+
+    /// ties the value cells from start up to end to the given uri
+    /// the id is optional, see Hyperlink
+    pub fn link(&mut self, start: usize, end: usize, uri: &str, id: Option<&str>) {
+        self.links.push(Hyperlink {
+            start,
+            end,
+            uri: uri.to_string(),
+            id: id.map(String::from),
+        });
+    }
+
+    /// writes a grapheme cluster, e.g., the text of a Char::Grapheme, into the cell under the cursor
+    /// as one unit and moves the cursor past it, by 2 cells for a wide cluster
+    /// a cluster that doesn't fit in the rest of the line goes at the start of the next one
+    ///
+    /// returns false if there was no room left for it
+    ///
+    /// # Examples
+    /// ```
+    /// if let Char::Grapheme(cluster) = &ke.char {
+    ///     text.insert_grapheme(cluster);
+    /// }
+    /// ```
+    pub fn insert_grapheme(&mut self, cluster: &str) -> bool {
+        let Some(first) = cluster.chars().next() else {
+            return false;
+        };
+        let cols = width(cluster).min(self.w as usize) as u16;
+
+        let [mut cx, mut cy] = [self.cx, self.cy];
+        if cx + cols > self.w {
+            [cx, cy] = [0, cy + 1];
+        }
+        if cy >= self.h {
+            return false;
+        }
+
+        let cell = (cx + cy * self.w) as usize;
+        self.value[cell] = Some(first);
+        if cols == 2 {
+            self.value[cell + 1] = None;
+            self.graphemes.remove(&(cell + 1));
+        }
+        match cluster.chars().nth(1).is_some() || cols == 2 {
+            true => self.graphemes.insert(cell, cluster.to_string()),
+            false => self.graphemes.remove(&cell),
+        };
+
+        self.cx = cx + cols;
+        self.cy = cy;
+        if self.cx == self.w && self.cy + 1 < self.h {
+            [self.cx, self.cy] = [0, self.cy + 1];
+        }
+
+        true
+    }
+
+    /// returns the grapheme cluster of the given value cell and how many columns it takes
+    /// None if the cell holds no cluster, or a char that replaced it
+    pub fn grapheme_at(&self, cell: usize) -> Option<(&str, usize)> {
+        let first = self.value.get(cell).copied().flatten()?;
+        self.graphemes
+            .get(&cell)
+            .filter(|cluster| cluster.starts_with(first))
+            .map(|cluster| (cluster.as_str(), width(cluster)))
+    }
+
+    /// returns the link of the given value cell and its index in links, if it has one
+    /// the last added link wins where spans overlap
+    pub fn link_at(&self, cell: usize) -> Option<(usize, &Hyperlink)> {
+        self.links
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, l)| l.contains(cell))
+    }
+
+    /// the id that render uses for the link at the given index when it has none
+    pub(crate) fn link_id(&self, idx: usize) -> String {
+        format!("ragout-{}-{}-{}-{}", self.id[0], self.id[1], self.id[2], idx)
+    }
+
     /// changes the border style of this text
     pub fn bstyle(&mut self, style: &Style) {
         self.bstyle = style.style();
@@ -1,24 +1,94 @@
+/// terminal capability detection through device attribute and mode queries
+pub mod capabilities;
+/// OSC 52 clipboard writes and reads
+pub mod clipboard;
+/// cursor shape, visibility and position queries
+pub mod cursor;
+/// window title and icon name, and the title stack
+pub mod title;
 /// termios c ffi, raw mode utilities
 pub mod raw_mode;
+/// poll c ffi, used to wait on stdin without blocking forever
+pub(crate) mod poll;
+/// RAII terminal session guard, restores the terminal state on drop and on panic
+pub mod session;
+/// signal c ffi, self pipe signal handling that wakes up the input reader
+pub mod signals;
+/// reads the compiled terminfo database
+pub mod terminfo;
 /// winsize c ffi, use for getting the terminal window widtn and height
 pub mod winsize;
 
-use std::io::StdoutLock;
 use std::io::Write;
 
-pub use raw_mode::{cooked_mode, raw_mode};
+pub use capabilities::{capabilities, Capabilities};
+pub use clipboard::{copy_to_clipboard, request_clipboard, ClipboardEvent, Selection};
+pub use cursor::{
+    cursor_position, hide_cursor, set_cursor_shape, show_cursor, CursorShape, CursorStyle,
+};
+pub use raw_mode::{cooked_mode, rare_mode, raw_mode, TermiosMeta};
+pub use session::{suspend, SessionMeta, TerminalSession};
+pub use terminfo::Terminfo;
+pub use title::{pop_title, push_title, set_icon_name, set_title, set_title_and_icon_name};
 
 /// exits the terminal alternate screen back to the original screen
-pub fn leave_alternate_screen(writer: &mut StdoutLock) {
+pub fn leave_alternate_screen(writer: &mut impl Write) {
     _ = writer.write(b"\x1b[?1049l");
 }
 
 /// move to the terminal alternate screen from the defaut one
-pub fn enter_alternate_screen(writer: &mut StdoutLock) {
+pub fn enter_alternate_screen(writer: &mut impl Write) {
     _ = writer.write(b"\x1b[?1049h\x1b[0;0f");
     _ = writer.flush();
 }
 
+/// enables bracketed paste mode
+/// pasted text then reaches the program wrapped in ESC[200~ and ESC[201~
+/// which inputs::event decodes into a PasteEvent
+pub fn enable_bracketed_paste(writer: &mut impl Write) {
+    _ = writer.write(b"\x1b[?2004h");
+}
+
+/// disables bracketed paste mode
+pub fn disable_bracketed_paste(writer: &mut impl Write) {
+    _ = writer.write(b"\x1b[?2004l");
+}
+
+/// starts a synchronized update
+/// the terminal holds its display until end_synchronized_update, then shows everything at once
+/// terminals that don't support mode 2026 ignore it
+pub fn begin_synchronized_update(writer: &mut impl Write) {
+    _ = writer.write(b"\x1b[?2026h");
+}
+
+/// ends a synchronized update
+pub fn end_synchronized_update(writer: &mut impl Write) {
+    _ = writer.write(b"\x1b[?2026l");
+}
+
+/// writes the given bytes as a single synchronized update, in a single write
+pub fn write_synchronized(writer: &mut impl Write, bytes: &[u8]) {
+    let mut frame = Vec::with_capacity(bytes.len() + 16);
+    frame.extend_from_slice(b"\x1b[?2026h");
+    frame.extend_from_slice(bytes);
+    frame.extend_from_slice(b"\x1b[?2026l");
+    _ = writer.write_all(&frame);
+}
+
 pub fn workers() -> (std::io::StdinLock<'static>, std::io::StdoutLock<'static>) {
     (std::io::stdin().lock(), std::io::stdout().lock())
 }
+
+#[cfg(test)]
+mod test_console {
+    use super::{begin_synchronized_update, end_synchronized_update, write_synchronized};
+
+    #[test]
+    fn synchronized() {
+        let mut bytes = vec![];
+        begin_synchronized_update(&mut bytes);
+        end_synchronized_update(&mut bytes);
+        write_synchronized(&mut bytes, b"frame");
+        assert_eq!(bytes, b"\x1b[?2026h\x1b[?2026l\x1b[?2026hframe\x1b[?2026l");
+    }
+}
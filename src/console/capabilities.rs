@@ -0,0 +1,248 @@
+use std::io::{StdoutLock, Write};
+use std::time::{Duration, Instant};
+
+use crate::inputs::{read_timeout, InputParser, InputSource, Interaction};
+
+/// the DEC private modes the probe asks about
+pub const PROBED_MODES: &[u16] = &[1000, 1002, 1003, 1004, 1006, 2004, 2026];
+
+/// what the terminal said it supports
+///
+/// filled by capabilities(), which queries the terminal,
+/// or by Capabilities::from_env(), which only reads the environment
+#[derive(Debug, Clone, Default)]
+pub struct Capabilities {
+    /// the TERM environment variable
+    pub term: Option<String>,
+    /// the COLORTERM environment variable
+    pub colorterm: Option<String>,
+    /// whether the terminal answered the queries at all
+    pub responded: bool,
+    /// the primary device attributes (DA1), e.g., 4 for sixel graphics, 22 for ansi colors
+    pub attributes: Vec<u16>,
+    /// the secondary device attributes (DA2); terminal type, firmware version and rom cartridge number
+    pub secondary: Vec<u16>,
+    /// the name and version of the terminal (XTVERSION), e.g., "kitty(0.31.0)"
+    pub version: Option<String>,
+    /// the kitty keyboard protocol flags currently pushed, None if the protocol is not supported
+    pub kitty_keyboard: Option<u8>,
+    /// the DEC private mode reports (DECRPM); the mode and its state
+    /// 0 for not recognized, 1 set, 2 reset, 3 permanently set and 4 permanently reset
+    modes: Vec<(u16, u8)>,
+}
+
+// terminals known to render truecolor even when COLORTERM does not make it through, e.g., over ssh
+const TRUECOLOR_TERMINALS: &[&str] = &[
+    "kitty",
+    "wezterm",
+    "alacritty",
+    "foot",
+    "ghostty",
+    "contour",
+    "iterm",
+    "konsole",
+    "direct",
+];
+
+impl Capabilities {
+    /// reads the TERM and COLORTERM environment variables, without querying the terminal
+    pub fn from_env() -> Self {
+        Self {
+            term: std::env::var("TERM").ok(),
+            colorterm: std::env::var("COLORTERM").ok(),
+            ..Default::default()
+        }
+    }
+
+    /// returns the state the terminal reported for the given DEC private mode
+    /// None if the mode was not probed or the terminal did not answer
+    pub fn mode(&self, mode: u16) -> Option<u8> {
+        self.modes.iter().find(|(m, _)| *m == mode).map(|(_, s)| *s)
+    }
+
+    /// returns whether the terminal recognizes the given DEC private mode
+    pub fn supports_mode(&self, mode: u16) -> bool {
+        matches!(self.mode(mode), Some(1..=4))
+    }
+
+    /// whether 24 bit rgb colors can be used
+    pub fn truecolor(&self) -> bool {
+        let known = |s: &Option<String>| {
+            s.as_ref().is_some_and(|s| {
+                let s = s.to_lowercase();
+                TRUECOLOR_TERMINALS.iter().any(|t| s.contains(t))
+            })
+        };
+
+        matches!(self.colorterm.as_deref(), Some("truecolor" | "24bit"))
+            || known(&self.term)
+            || known(&self.version)
+    }
+
+    /// whether the 256 colors palette can be used
+    pub fn colors_256(&self) -> bool {
+        self.truecolor() || self.term.as_ref().is_some_and(|t| t.contains("256color"))
+    }
+
+    /// whether mouse reporting in the sgr format is supported
+    pub fn mouse(&self) -> bool {
+        self.supports_mode(1000) && self.supports_mode(1006)
+    }
+
+    /// whether bracketed paste mode is supported
+    pub fn bracketed_paste(&self) -> bool {
+        self.supports_mode(2004)
+    }
+
+    /// whether focus reporting is supported
+    pub fn focus_reporting(&self) -> bool {
+        self.supports_mode(1004)
+    }
+
+    /// whether synchronized output is supported
+    pub fn synchronized_output(&self) -> bool {
+        self.supports_mode(2026)
+    }
+
+    // reads one terminal reply into the capabilities
+    // returns true for the DA1 reply, the last one to come
+    fn parse_reply(&mut self, reply: &[u8]) -> bool {
+        let Some(body) = reply.strip_prefix(b"\x1b[") else {
+            // XTVERSION; DCS > | text ST
+            if let Some(text) = reply.strip_prefix(b"\x1bP>|") {
+                let text = text.strip_suffix(b"\x1b\\").unwrap_or(text);
+                self.version = Some(String::from_utf8_lossy(text).into_owned());
+            }
+            return false;
+        };
+
+        match body {
+            [b'?', params @ .., b'$', b'y'] => {
+                let params = numbers(params);
+                if let [mode, state] = params[..] {
+                    self.modes.push((mode, state as u8));
+                }
+            }
+            [b'?', params @ .., b'u'] => {
+                self.kitty_keyboard = numbers(params).first().map(|flags| *flags as u8);
+            }
+            [b'>', params @ .., b'c'] => self.secondary = numbers(params),
+            [b'?', params @ .., b'c'] => {
+                self.attributes = numbers(params);
+                return true;
+            }
+            _ => (),
+        }
+
+        false
+    }
+}
+
+// ; separated numbers, the ones that fail to parse are left out
+fn numbers(params: &[u8]) -> Vec<u16> {
+    String::from_utf8_lossy(params)
+        .split(';')
+        .filter_map(|n| n.parse().ok())
+        .collect()
+}
+
+/// queries the terminal for its capabilities and waits for the replies for at most the given timeout
+///
+/// sends the DECRQM queries for the PROBED_MODES, a kitty keyboard protocol query,
+/// XTVERSION, DA2 and finally DA1, which every terminal answers;
+/// its reply ends the wait early
+///
+/// the terminal has to be in raw mode for the replies to arrive right away.
+/// any input typed while the probe runs gets dropped
+///
+/// # Examples
+/// ```
+/// let ts = raw_mode();
+/// let caps = capabilities(&mut reader, &mut writer, Duration::from_millis(200));
+/// if caps.mouse() {
+///     enable_mouse_input(&mut writer, Tracking::Motion);
+/// }
+/// ```
+pub fn capabilities<S: InputSource>(
+    reader: &mut S,
+    writer: &mut StdoutLock,
+    timeout: Duration,
+) -> Capabilities {
+    let mut queries = String::new();
+    for mode in PROBED_MODES {
+        queries.push_str(&format!("\x1b[?{}$p", mode));
+    }
+    queries.push_str("\x1b[?u\x1b[>0q\x1b[>c\x1b[c");
+    _ = writer.write(queries.as_bytes());
+    _ = writer.flush();
+
+    let mut caps = Capabilities::from_env();
+    let mut parser = InputParser::new();
+    let mut buf = vec![];
+    let deadline = Instant::now() + timeout;
+
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        let events = match read_timeout(reader, &mut buf, left) {
+            Some(bytes) => parser.feed(bytes),
+            None => parser.finish(),
+        };
+
+        for ie in events {
+            if let Interaction::Unknown(reply) = ie.event {
+                caps.responded = true;
+                if caps.parse_reply(&reply) {
+                    return caps;
+                }
+            }
+        }
+
+        if left.is_zero() {
+            return caps;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_capabilities {
+    use super::Capabilities;
+
+    #[test]
+    fn replies() {
+        let mut caps = Capabilities::default();
+        assert!(!caps.parse_reply(b"\x1b[?1000;2$y"));
+        assert!(!caps.parse_reply(b"\x1b[?1006;1$y"));
+        assert!(!caps.parse_reply(b"\x1b[?2026;0$y"));
+        assert!(!caps.parse_reply(b"\x1b[?1004;4$y"));
+        assert!(!caps.parse_reply(b"\x1b[?0u"));
+        assert!(!caps.parse_reply(b"\x1bP>|WezTerm 20240203\x1b\\"));
+        assert!(!caps.parse_reply(b"\x1b[>1;4000;0c"));
+        assert!(caps.parse_reply(b"\x1b[?62;22;4c"));
+
+        assert!(caps.mouse());
+        assert!(caps.focus_reporting());
+        assert!(!caps.synchronized_output());
+        assert!(!caps.bracketed_paste());
+        assert_eq!(caps.mode(1000), Some(2));
+        assert_eq!(caps.kitty_keyboard, Some(0));
+        assert_eq!(caps.version.as_deref(), Some("WezTerm 20240203"));
+        assert_eq!(caps.secondary, [1, 4000, 0]);
+        assert_eq!(caps.attributes, [62, 22, 4]);
+        assert!(caps.truecolor());
+    }
+
+    #[test]
+    fn colors() {
+        let caps = |term: &str, colorterm: &str| Capabilities {
+            term: Some(term.to_string()),
+            colorterm: Some(colorterm.to_string()),
+            ..Default::default()
+        };
+
+        assert!(caps("xterm-256color", "truecolor").truecolor());
+        assert!(caps("xterm-kitty", "").truecolor());
+        assert!(!caps("xterm-256color", "").truecolor());
+        assert!(caps("xterm-256color", "").colors_256());
+        assert!(!caps("vt100", "").colors_256());
+    }
+}
@@ -0,0 +1,162 @@
+use std::io::{StdoutLock, Write};
+
+// the OSC 52 sequence; ESC ] 52 ; selection ; base64 data ST
+const OSC52: &[u8] = b"\x1b]52;";
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// the selection buffers OSC 52 can access
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Selection {
+    /// the system clipboard, what ctrl+c and ctrl+v use
+    #[default]
+    Clipboard,
+    /// the X11 primary selection, what middle click pastes
+    Primary,
+}
+
+impl Selection {
+    fn code(&self) -> char {
+        match self {
+            Self::Clipboard => 'c',
+            Self::Primary => 'p',
+        }
+    }
+}
+
+/// the clipboard contents, as the terminal replied to request_clipboard
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClipboardEvent {
+    pub selection: Selection,
+    pub text: String,
+}
+
+/// writes the given text into the given selection through the terminal (OSC 52)
+/// works over ssh, since the terminal on the user side does the copying
+///
+/// some terminals need OSC 52 to be allowed in their settings, and cap the size of the text
+pub fn copy_to_clipboard(writer: &mut StdoutLock, selection: Selection, text: &str) {
+    let mut seq = OSC52.to_vec();
+    seq.extend_from_slice(format!("{};", selection.code()).as_bytes());
+    seq.extend_from_slice(encode(text.as_bytes()).as_bytes());
+    seq.extend_from_slice(b"\x1b\\");
+    _ = writer.write_all(&seq);
+    _ = writer.flush();
+}
+
+/// asks the terminal for the contents of the given selection (OSC 52)
+/// the reply comes as an Interaction::ClipboardEvent among the next input events
+///
+/// many terminals refuse clipboard reads by default, in which case nothing comes back
+pub fn request_clipboard(writer: &mut StdoutLock, selection: Selection) {
+    _ = writer.write(format!("\x1b]52;{};?\x1b\\", selection.code()).as_bytes());
+    _ = writer.flush();
+}
+
+/// decodes an OSC 52 clipboard reply, terminated by either BEL or ST
+/// returns None if the bytes are not one
+pub fn decode_clipboard(bytes: &[u8]) -> Option<ClipboardEvent> {
+    let body = bytes.strip_prefix(OSC52)?;
+    let body = body
+        .strip_suffix(b"\x07")
+        .or_else(|| body.strip_suffix(b"\x1b\\"))?;
+
+    let sep = body.iter().position(|b| *b == b';')?;
+    let (selection, data) = (&body[..sep], &body[sep + 1..]);
+    // the terminal can list many selections; it only uses the first one
+    let selection = match selection.first() {
+        Some(b'p') => Selection::Primary,
+        _ => Selection::Clipboard,
+    };
+
+    Some(ClipboardEvent {
+        selection,
+        text: String::from_utf8_lossy(&decode(data)?).into_owned(),
+    })
+}
+
+fn encode(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (idx, b)| n | ((*b as u32) << (16 - idx * 8)));
+        for idx in 0..4 {
+            match idx <= chunk.len() {
+                true => s.push(BASE64[(n >> (18 - idx * 6)) as usize & 63] as char),
+                false => s.push('='),
+            }
+        }
+    }
+
+    s
+}
+
+// padding is optional, anything outside the alphabet is an error
+fn decode(s: &[u8]) -> Option<Vec<u8>> {
+    let s = s
+        .strip_suffix(b"==")
+        .or_else(|| s.strip_suffix(b"="))
+        .unwrap_or(s);
+    if s.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut bytes = Vec::with_capacity(s.len() / 4 * 3);
+    for chunk in s.chunks(4) {
+        let mut n = 0u32;
+        for (idx, c) in chunk.iter().enumerate() {
+            let v = BASE64.iter().position(|b| b == c)? as u32;
+            n |= v << (18 - idx * 6);
+        }
+        for idx in 0..chunk.len() - 1 {
+            bytes.push((n >> (16 - idx * 8)) as u8);
+        }
+    }
+
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod test_clipboard {
+    use super::{decode, decode_clipboard, encode, ClipboardEvent, Selection};
+
+    #[test]
+    fn base64() {
+        assert_eq!(encode(b""), "");
+        assert_eq!(encode(b"f"), "Zg==");
+        assert_eq!(encode(b"fo"), "Zm8=");
+        assert_eq!(encode(b"foo"), "Zm9v");
+        assert_eq!(encode(b"foobar"), "Zm9vYmFy");
+
+        let text = "line 1\n\tلا ✓ \u{0}\u{ff}";
+        assert_eq!(
+            decode(encode(text.as_bytes()).as_bytes()).unwrap(),
+            text.as_bytes()
+        );
+        assert_eq!(decode(b"Zm8").unwrap(), b"fo");
+        assert!(decode(b"Zm9vY").is_none());
+        assert!(decode(b"Zm-v").is_none());
+    }
+
+    #[test]
+    fn reply() {
+        assert_eq!(
+            decode_clipboard(b"\x1b]52;c;aGVsbG8=\x07"),
+            Some(ClipboardEvent {
+                selection: Selection::Clipboard,
+                text: "hello".to_string(),
+            })
+        );
+        assert_eq!(
+            decode_clipboard(b"\x1b]52;p;\x1b\\"),
+            Some(ClipboardEvent {
+                selection: Selection::Primary,
+                text: String::new(),
+            })
+        );
+        assert!(decode_clipboard(b"\x1b]52;c;aGVsbG8=").is_none());
+        assert!(decode_clipboard(b"\x1b]11;rgb:0000/0000/0000\x07").is_none());
+    }
+}
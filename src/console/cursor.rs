@@ -0,0 +1,207 @@
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+use super::session::mark_cursor;
+use crate::inputs::{read_timeout, InputSource};
+
+/// the cursor shapes that can be set with DECSCUSR
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CursorShape {
+    /// whatever shape the user configured the terminal with
+    #[default]
+    Default,
+    BlinkingBlock,
+    SteadyBlock,
+    BlinkingUnderline,
+    SteadyUnderline,
+    BlinkingBar,
+    SteadyBar,
+}
+
+impl CursorShape {
+    /// returns the DECSCUSR parameter of this shape
+    pub fn code(&self) -> u8 {
+        match self {
+            Self::Default => 0,
+            Self::BlinkingBlock => 1,
+            Self::SteadyBlock => 2,
+            Self::BlinkingUnderline => 3,
+            Self::SteadyUnderline => 4,
+            Self::BlinkingBar => 5,
+            Self::SteadyBar => 6,
+        }
+    }
+}
+
+/// the shape and visibility the cursor should have
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CursorStyle {
+    pub shape: CursorShape,
+    pub visible: bool,
+}
+
+impl Default for CursorStyle {
+    fn default() -> Self {
+        Self {
+            shape: CursorShape::Default,
+            visible: true,
+        }
+    }
+}
+
+impl CursorStyle {
+    /// a visible cursor with the default shape
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// sets the cursor shape
+    pub fn shape(mut self, shape: CursorShape) -> Self {
+        self.shape = shape;
+        self
+    }
+
+    /// sets the cursor visibility
+    pub fn visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+
+    /// returns the escape sequences that apply this style
+    pub fn sequence(&self) -> String {
+        format!(
+            "\x1b[{} q\x1b[?25{}",
+            self.shape.code(),
+            if self.visible { 'h' } else { 'l' }
+        )
+    }
+
+    /// writes this style to the terminal
+    pub fn apply(&self, writer: &mut impl Write) {
+        _ = writer.write(self.sequence().as_bytes());
+        mark_cursor(*self != Self::default());
+    }
+}
+
+/// hides the cursor
+pub fn hide_cursor(writer: &mut impl Write) {
+    _ = writer.write(b"\x1b[?25l");
+    mark_cursor(true);
+}
+
+/// shows the cursor
+pub fn show_cursor(writer: &mut impl Write) {
+    _ = writer.write(b"\x1b[?25h");
+}
+
+/// sets the cursor shape
+pub fn set_cursor_shape(writer: &mut impl Write, shape: CursorShape) {
+    _ = writer.write(format!("\x1b[{} q", shape.code()).as_bytes());
+    // the default shape says nothing about the visibility
+    if shape != CursorShape::Default {
+        mark_cursor(true);
+    }
+}
+
+/// asks the terminal where the cursor is (DSR 6) and waits for the reply for at most the given timeout
+/// returns the 1 based [column, row] of the cursor, or None if the terminal did not answer
+///
+/// the terminal has to be in raw mode for the reply to arrive right away.
+/// any input typed while waiting gets dropped
+///
+/// # Examples
+/// ```
+/// let ts = raw_mode();
+/// if let Some([x, y]) = cursor_position(&mut reader, &mut writer, Duration::from_millis(100)) {
+///     term.cx = x;
+///     term.cy = y;
+/// }
+/// ```
+pub fn cursor_position<S: InputSource>(
+    reader: &mut S,
+    writer: &mut impl Write,
+    timeout: Duration,
+) -> Option<[u16; 2]> {
+    _ = writer.write(b"\x1b[6n");
+    _ = writer.flush();
+
+    let mut buf = vec![];
+    let mut replies = vec![];
+    let deadline = Instant::now() + timeout;
+
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if let Some(bytes) = read_timeout(reader, &mut buf, left) {
+            replies.extend_from_slice(bytes);
+            if let Some(pos) = parse_position(&replies) {
+                return Some(pos);
+            }
+        }
+
+        if left.is_zero() {
+            return None;
+        }
+    }
+}
+
+// finds the cursor position report, ESC [ row ; col R, in the given bytes
+// the reply is read raw, the input parser would take it for a modified F3
+fn parse_position(bytes: &[u8]) -> Option<[u16; 2]> {
+    bytes
+        .windows(2)
+        .enumerate()
+        .filter(|(_, w)| w == b"\x1b[")
+        .find_map(|(idx, _)| {
+            let body = &bytes[idx + 2..];
+            let end = body.iter().position(|b| !matches!(b, b'0'..=b'9' | b';'))?;
+            if body[end] != b'R' {
+                return None;
+            }
+
+            let body = std::str::from_utf8(&body[..end]).ok()?;
+            let (row, col) = body.split_once(';')?;
+
+            Some([col.parse().ok()?, row.parse().ok()?])
+        })
+}
+
+#[cfg(test)]
+mod test_cursor {
+    use super::{
+        hide_cursor, parse_position, set_cursor_shape, show_cursor, CursorShape, CursorStyle,
+    };
+    use crate::console::session::SERIAL;
+
+    #[test]
+    fn position() {
+        assert_eq!(parse_position(b"\x1b[12;40R"), Some([40, 12]));
+        assert_eq!(parse_position(b"a\x1b[A\x1b[1;1R"), Some([1, 1]));
+        assert_eq!(parse_position(b"\x1b[12;40"), None);
+        assert_eq!(parse_position(b"\x1b[12R"), None);
+        assert_eq!(parse_position(b"\x1b[2~"), None);
+    }
+
+    #[test]
+    fn style() {
+        assert_eq!(CursorStyle::default().sequence(), "\x1b[0 q\x1b[?25h");
+        assert_eq!(
+            CursorStyle::new()
+                .shape(CursorShape::SteadyBar)
+                .visible(false)
+                .sequence(),
+            "\x1b[6 q\x1b[?25l"
+        );
+    }
+
+    #[test]
+    fn written() {
+        // these mark the cursor of a session a test may have started
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+
+        let mut bytes = vec![];
+        hide_cursor(&mut bytes);
+        set_cursor_shape(&mut bytes, CursorShape::BlinkingUnderline);
+        show_cursor(&mut bytes);
+        assert_eq!(bytes, b"\x1b[?25l\x1b[3 q\x1b[?25h");
+    }
+}
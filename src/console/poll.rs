@@ -0,0 +1,62 @@
+// from /usr/include/sys/poll.h
+extern "C" {
+    fn poll(__fds: *mut pollfd, __nfds: nfds_t, __timeout: i32) -> i32;
+}
+
+/// ffi bindings for the c struct found in '/usr/include/sys/poll.h'
+#[derive(Debug, Default, Clone)]
+#[repr(C)]
+struct pollfd {
+    fd: i32,
+    events: i16,
+    revents: i16,
+}
+
+#[cfg(not(target_os = "macos"))]
+type nfds_t = u64;
+#[cfg(target_os = "macos")]
+type nfds_t = u32;
+
+// from /usr/include/bits/poll.h
+const POLLIN: i16 = 0x001;
+const POLLERR: i16 = 0x008;
+const POLLHUP: i16 = 0x010;
+const POLLNVAL: i16 = 0x020;
+
+/// waits until at least one of the given file descriptors has data to be read
+/// a negative timeout (in milliseconds) waits forever
+///
+/// returns whether each fd is readable, in the same order they were given
+/// an fd that hung up, failed or isn't open counts as readable, its read then reports the error
+/// all false means that the timeout expired or that a signal interrupted the wait
+pub(crate) fn readable(fds: &[i32], timeout: i32) -> Vec<bool> {
+    let mut pfds = fds
+        .iter()
+        .map(|fd| pollfd {
+            fd: *fd,
+            events: POLLIN,
+            revents: 0,
+        })
+        .collect::<Vec<pollfd>>();
+
+    let n = unsafe { poll(pfds.as_mut_ptr(), pfds.len() as nfds_t, timeout) };
+    if n <= 0 {
+        return vec![false; fds.len()];
+    }
+
+    pfds.iter()
+        .map(|p| p.revents & (POLLIN | POLLHUP | POLLERR | POLLNVAL) != 0)
+        .collect()
+}
+
+#[cfg(test)]
+mod test_poll {
+    use super::readable;
+
+    #[test]
+    fn invalid_fd() {
+        // an fd that isn't open doesn't leave the wait hanging until the timeout
+        // negative fds are skipped by poll, the biggest one is never open
+        assert_eq!(readable(&[i32::MAX], 1000), [true]);
+    }
+}
@@ -18,12 +18,16 @@ pub struct termios {
     c_cflag: tcflag_t,
     c_lflag: tcflag_t,
     c_line: cc_t,
-    c_cc: cc_t,
+    c_cc: [cc_t; NCCS],
+    c_ispeed: speed_t,
+    c_ospeed: speed_t,
 }
 
-// from /usr/include/libr/sflib/common/sftypes.h
-type cc_t = [u8; 8];
+// from /usr/include/bits/termios-struct.h
+type cc_t = u8;
+type speed_t = u32;
 type tcflag_t = u32;
+const NCCS: usize = 32;
 
 // from /usr/include/libr/sflib/common/sftypes.h
 const TCSANOW: i32 = 0;
@@ -38,48 +42,184 @@ const STDERR_FILENO: i32 = 2;
 // It is recommended to read [this]('https://www.gnu.org/software/libc/manual/html_node/Input-Modes.html') before using the tcsetattr fn directly if you don't know what the various fileds of termios are and what their flags do
 // Otherwise, if you intend to only use tcgetattr or the abstract enable/disable_raw/rare_mode fns then no reading is required
 // flags definitions can be found in these header files: '/usr/include/bits/termios-c_*.h'
+// NOTE: the values in those headers are octal
 // Recommended to read [this]('https://smnd.sk/anino/programming/c/unix_examples/raw.html') if you want to understand the reasoning behind the particular flag configuration of raw_mode
 
 // # [important]("https://www.gnu.org/software/libc/manual/html_node/Terminal-Modes.html")
 // # lflag bits
 // turning this flag off means input won't be displayed on the terminal anymore
-const ECHO: u32 = 10;
+const ECHO: u32 = 0o10;
 // turning this flag off disables the INTR, QUIT and SUSP signals
 // basically, when this is off, stuff like ctrl-c (SIGINT) won't work
 // turning this bit off renders c_cc's special characters off
-const ISIG: u32 = 1;
+const ISIG: u32 = 0o1;
 // canonical input mode gives meaning to escape sequences,
 // without it 'Backspace' would not delete the char behind cursor and 'Enter' will not submit input
-const ICANON: u32 = 2;
+const ICANON: u32 = 0o2;
 //
-const IEXTEN: u32 = 100_000;
+const IEXTEN: u32 = 0o100000;
 
 // # iflag bits
 // disabling this disables signal interrupt on break, we don't want it in raw mode
-const BRKINT: u32 = 2;
+const BRKINT: u32 = 0o2;
 // when this is enabled input is checked for parity,
 // this bit is a pair with cflag's PARENB bit
-const INPCK: u32 = 20;
+const INPCK: u32 = 0o20;
 // this strips the 8th bit off an input ascii char / byte
-const ISTRIP: u32 = 40;
+const ISTRIP: u32 = 0o40;
 // maps LF to CR
-const INLCR: u32 = 100;
+const INLCR: u32 = 0o100;
 // this bit maps CR to NL, meaning that it automatically prepends a cr (\r) on lf (\n)
-const ICRNL: u32 = 400;
+const ICRNL: u32 = 0o400;
 // machine ouput start/stop control
 // we'll turn this off, but keep its input counterpart
-const IXON: u32 = 2000;
+const IXON: u32 = 0o2000;
 
 // # oflag bits
 // this bit enables output post processing, processes output before displaying it so that it is
 // rendered correctly on the terminal
-const OPOST: u32 = 1;
+const OPOST: u32 = 0o1;
 
 // # cflag bits
+// the character size mask
+const CSIZE: u32 = 0o60;
 // specifies that a byte will be 8 bits
-const CS8: u32 = 60;
+const CS8: u32 = 0o60;
 // when this is enabled a parity bit is added to output values
-const PARENB: u32 = 400;
+const PARENB: u32 = 0o400;
+
+// # c_cc indices
+// from /usr/include/bits/termios-c_cc.h
+// in non canonical mode, a read returns once it has VTIME tenths of a second passed or VMIN bytes
+const VTIME: usize = 5;
+const VMIN: usize = 6;
+
+impl termios {
+    /// creates a new termios instance with the current terminal flags
+    pub fn from_tcgetattr() -> Self {
+        let mut ts = Self::default();
+        _ = unsafe { tcgetattr(STDIN_FILENO, &mut ts) };
+
+        ts
+    }
+
+    /// applies this termios flags to the terminal
+    /// returns the tcsetattr result, 0 on success and -1 on failure
+    pub fn tcsetattr(&self) -> i32 {
+        unsafe { tcsetattr(STDIN_FILENO, TCSANOW, self) }
+    }
+}
+
+/// builder for a custom terminal mode
+///
+/// every flag that is not toggled keeps the value it has in the current terminal mode
+///
+/// # Examples
+/// ```
+/// // unbuffered input without echo, ctrl-c still sends SIGINT
+/// let original = TermiosMeta::new()
+///     .echo(false)
+///     .icanon(false)
+///     .vmin(1)
+///     .vtime(0)
+///     .apply();
+///
+/// cooked_mode(original);
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct TermiosMeta {
+    echo: Option<bool>,
+    icanon: Option<bool>,
+    isig: Option<bool>,
+    ixon: Option<bool>,
+    opost: Option<bool>,
+    vmin: Option<u8>,
+    vtime: Option<u8>,
+}
+
+impl TermiosMeta {
+    /// creates a new TermiosMeta that leaves every flag untouched
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// toggles the echoing of input back to the terminal
+    pub fn echo(mut self, on: bool) -> Self {
+        self.echo = Some(on);
+        self
+    }
+
+    /// toggles canonical (line buffered) input
+    pub fn icanon(mut self, on: bool) -> Self {
+        self.icanon = Some(on);
+        self
+    }
+
+    /// toggles the INTR, QUIT and SUSP signal chars (ctrl-c, ctrl-\, ctrl-z)
+    pub fn isig(mut self, on: bool) -> Self {
+        self.isig = Some(on);
+        self
+    }
+
+    /// toggles ctrl-s/ctrl-q output flow control
+    pub fn ixon(mut self, on: bool) -> Self {
+        self.ixon = Some(on);
+        self
+    }
+
+    /// toggles output post processing, e.g., '\n' being output as '\r\n'
+    pub fn opost(mut self, on: bool) -> Self {
+        self.opost = Some(on);
+        self
+    }
+
+    /// the minimum number of bytes a non canonical read waits for
+    pub fn vmin(mut self, vmin: u8) -> Self {
+        self.vmin = Some(vmin);
+        self
+    }
+
+    /// the time a non canonical read waits for, in tenths of a second
+    pub fn vtime(mut self, vtime: u8) -> Self {
+        self.vtime = Some(vtime);
+        self
+    }
+
+    /// returns a copy of the given termios with this meta's flags applied on top of it
+    pub fn build(&self, base: &termios) -> termios {
+        let mut ts = base.clone();
+
+        let toggle = |flags: &mut tcflag_t, bit: tcflag_t, on: Option<bool>| match on {
+            Some(true) => *flags |= bit,
+            Some(false) => *flags &= !bit,
+            None => (),
+        };
+
+        toggle(&mut ts.c_lflag, ECHO, self.echo);
+        toggle(&mut ts.c_lflag, ICANON, self.icanon);
+        toggle(&mut ts.c_lflag, ISIG, self.isig);
+        toggle(&mut ts.c_iflag, IXON, self.ixon);
+        toggle(&mut ts.c_oflag, OPOST, self.opost);
+
+        if let Some(vmin) = self.vmin {
+            ts.c_cc[VMIN] = vmin;
+        }
+        if let Some(vtime) = self.vtime {
+            ts.c_cc[VTIME] = vtime;
+        }
+
+        ts
+    }
+
+    /// applies this meta's flags to the terminal
+    /// returns the original terminal flags for use with cooked_mode
+    pub fn apply(&self) -> termios {
+        let original = termios::from_tcgetattr();
+        self.build(&original).tcsetattr();
+
+        original
+    }
+}
 
 /// enables raw mode through disabling the relevant terminal flags - mainly ECHO and CANONICAL mode
 /// returns the original terminal flags in a termios instance for use when disabling raw mode
@@ -92,8 +232,10 @@ pub fn raw_mode() -> termios {
         raw.c_lflag &= !(ISIG | ICANON | ECHO | IEXTEN);
         raw.c_iflag &= !(BRKINT | INPCK | ISTRIP | INLCR | ICRNL | IXON);
         raw.c_oflag &= !OPOST;
-        raw.c_cflag &= !PARENB;
+        raw.c_cflag &= !(CSIZE | PARENB);
         raw.c_cflag |= CS8;
+        raw.c_cc[VMIN] = 1;
+        raw.c_cc[VTIME] = 0;
 
         let _res = tcsetattr(STDIN_FILENO, TCSANOW, &raw);
 
@@ -110,4 +252,53 @@ pub fn cooked_mode(original: termios) -> i32 {
 /// enables rare mode
 /// a terminal mode that is between cooked and raw
 /// in terms of features and functionality
-fn rare_mode() {}
+///
+/// keys are delivered unbuffered and without echo, like in raw mode,
+/// but ctrl-c, ctrl-\ and ctrl-z still send their signals and output is still post processed
+/// returns the original terminal flags for use with cooked_mode
+pub fn rare_mode() -> termios {
+    TermiosMeta::new()
+        .echo(false)
+        .icanon(false)
+        .isig(true)
+        .vmin(1)
+        .vtime(0)
+        .apply()
+}
+
+#[cfg(test)]
+mod test_termios {
+    use super::{termios, TermiosMeta, ECHO, ICANON, ISIG, IXON, OPOST, VMIN, VTIME};
+
+    #[test]
+    fn layout() {
+        // glibc struct termios is 60 bytes on linux
+        assert_eq!(std::mem::size_of::<termios>(), 60);
+    }
+
+    #[test]
+    fn build() {
+        let mut base = termios::default();
+        base.c_lflag = ECHO | ICANON | ISIG;
+        base.c_iflag = IXON;
+        base.c_oflag = OPOST;
+
+        let ts = TermiosMeta::new()
+            .echo(false)
+            .icanon(false)
+            .vmin(1)
+            .vtime(3)
+            .build(&base);
+
+        assert_eq!(ts.c_lflag, ISIG);
+        assert_eq!(ts.c_iflag, IXON);
+        assert_eq!(ts.c_oflag, OPOST);
+        assert_eq!(ts.c_cc[VMIN], 1);
+        assert_eq!(ts.c_cc[VTIME], 3);
+
+        let ts = TermiosMeta::new().isig(false).ixon(false).opost(false).build(&ts);
+        assert_eq!(ts.c_lflag, 0);
+        assert_eq!(ts.c_iflag, 0);
+        assert_eq!(ts.c_oflag, 0);
+    }
+}
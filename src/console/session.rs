@@ -0,0 +1,571 @@
+use std::io::Error;
+use std::io::Write;
+use std::sync::{Mutex, Once, TryLockError};
+use std::thread::{self, ThreadId};
+
+use super::cursor::CursorStyle;
+use super::raw_mode::{cooked_mode, raw_mode, termios};
+use super::signals;
+use super::title::{pop_title, push_title};
+use super::{disable_bracketed_paste, enable_bracketed_paste};
+use super::{enter_alternate_screen, leave_alternate_screen};
+use crate::inputs::keyboard::{
+    disable_keypad_mode, disable_kitty_keyboard, enable_keypad_mode, enable_kitty_keyboard,
+};
+use crate::inputs::mouse::{disable_mouse_input, enable_mouse_input, Tracking};
+use crate::inputs::window::{disable_focus_reporting, enable_focus_reporting, suspend};
+
+// from /usr/include/unistd.h
+extern "C" {
+    fn write(__fd: i32, __buf: *const u8, __n: usize) -> isize;
+}
+
+const STDOUT_FILENO: i32 = 1;
+
+// the state of the currently active session
+// kept in a global so that the panic hook can restore the terminal
+// even when the TerminalSession value is out of reach
+static ACTIVE: Mutex<Option<SessionState>> = Mutex::new(None);
+
+// leaves origin mode and resets the scroll region, both set by an inline Term
+const RESET_REGION: &[u8] = b"\x1b[?6l\x1b[r";
+
+#[derive(Debug)]
+struct SessionState {
+    /// the termios flags saved before raw mode was enabled
+    original: Option<termios>,
+    /// the modes that were applied by the session
+    meta: SessionMeta,
+    /// whether the modes were taken down for a suspension
+    suspended: bool,
+    /// whether the cursor may have been hidden or reshaped since the session started
+    cursor: bool,
+    /// whether an inline Term may have set a scroll region and origin mode
+    region: bool,
+    /// the thread that started the session, the only one whose panics restore the terminal
+    thread: ThreadId,
+}
+
+/// describes the terminal modes a TerminalSession applies on creation
+/// every mode that gets applied is restored when the session is dropped
+#[derive(Debug, Clone, Default)]
+pub struct SessionMeta {
+    raw: bool,
+    alternate_screen: bool,
+    mouse: bool,
+    tracking: Tracking,
+    bracketed_paste: bool,
+    kitty_keyboard: u8,
+    keypad_mode: bool,
+    focus_reporting: bool,
+    save_title: bool,
+    job_control: bool,
+}
+
+impl SessionMeta {
+    /// creates a new SessionMeta with all modes turned off
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// toggles raw mode on session start
+    pub fn raw(mut self, raw: bool) -> Self {
+        self.raw = raw;
+        self
+    }
+
+    /// toggles the alternate screen on session start
+    pub fn alternate_screen(mut self, alternate_screen: bool) -> Self {
+        self.alternate_screen = alternate_screen;
+        self
+    }
+
+    /// toggles raw mouse input on session start
+    pub fn mouse(mut self, mouse: bool) -> Self {
+        self.mouse = mouse;
+        self
+    }
+
+    /// sets which mouse events get reported when raw mouse input is on
+    /// defaults to Tracking::Motion
+    pub fn mouse_tracking(mut self, tracking: Tracking) -> Self {
+        self.tracking = tracking;
+        self
+    }
+
+    /// toggles bracketed paste mode on session start
+    pub fn bracketed_paste(mut self, bracketed_paste: bool) -> Self {
+        self.bracketed_paste = bracketed_paste;
+        self
+    }
+
+    /// pushes the given kitty keyboard protocol flags on session start, 0 leaves the protocol off
+    /// see inputs::keyboard::kitty for the flags
+    pub fn kitty_keyboard(mut self, flags: u8) -> Self {
+        self.kitty_keyboard = flags;
+        self
+    }
+
+    /// toggles the application keypad mode on session start
+    /// the keypad keys then get reported as CC::Keypad0 etc. instead of digits and operators
+    pub fn keypad_mode(mut self, keypad_mode: bool) -> Self {
+        self.keypad_mode = keypad_mode;
+        self
+    }
+
+    /// toggles focus reporting on session start
+    /// the terminal window gaining and losing the focus then gets reported as WindowEvents
+    pub fn focus_reporting(mut self, focus_reporting: bool) -> Self {
+        self.focus_reporting = focus_reporting;
+        self
+    }
+
+    /// toggles saving the window title on session start
+    /// the title the user had gets restored when the session ends, whatever the program set it to
+    pub fn save_title(mut self, save_title: bool) -> Self {
+        self.save_title = save_title;
+        self
+    }
+
+    /// toggles job control on session start
+    /// a SIGTSTP, e.g., ctrl+z with the ISIG flag on, then restores the terminal before the program stops
+    /// and a SIGCONT applies the session modes again and gets reported as a WindowEvent::Resumed
+    ///
+    /// raw mode turns ISIG off, ctrl+z then comes in as a key; call console::suspend on it
+    pub fn job_control(mut self, job_control: bool) -> Self {
+        self.job_control = job_control;
+        self
+    }
+
+    /// applies the requested modes and returns the session guard
+    ///
+    /// # Errors
+    ///
+    /// returns an error if another TerminalSession is still active
+    pub fn start(self) -> Result<TerminalSession, Error> {
+        TerminalSession::start(self)
+    }
+}
+
+/// RAII guard over the terminal state
+///
+/// applies the requested modes on creation and restores the saved termios,
+/// screen and mouse state when dropped.
+/// a panic hook is also installed, which restores the terminal before the panic message gets printed
+/// when the thread that started the session panics
+///
+/// # Examples
+/// ```
+/// let session = SessionMeta::new()
+///     .raw(true)
+///     .alternate_screen(true)
+///     .mouse(true)
+///     .start()
+///     .unwrap();
+///
+/// // event loop
+///
+/// // the terminal gets restored here
+/// drop(session);
+/// ```
+#[derive(Debug)]
+pub struct TerminalSession {
+    // only obtainable through SessionMeta::start
+    _private: (),
+}
+
+impl TerminalSession {
+    /// starts a new session with the modes described by the given SessionMeta
+    ///
+    /// # Errors
+    ///
+    /// returns an error if another TerminalSession is still active
+    pub fn start(meta: SessionMeta) -> Result<Self, Error> {
+        let mut active = ACTIVE.lock().unwrap_or_else(|e| e.into_inner());
+        if active.is_some() {
+            return Err(Error::other("a terminal session is already active"));
+        }
+
+        install_panic_hook();
+
+        let original = if meta.raw { Some(raw_mode()) } else { None };
+
+        let mut writer = std::io::stdout().lock();
+        if meta.save_title {
+            push_title(&mut writer);
+        }
+        apply(&meta, &mut writer);
+        _ = writer.flush();
+
+        if meta.job_control {
+            suspend::listen();
+        }
+
+        *active = Some(SessionState {
+            original,
+            meta,
+            suspended: false,
+            cursor: false,
+            region: false,
+            thread: thread::current().id(),
+        });
+
+        Ok(Self { _private: () })
+    }
+
+    /// restores the terminal state right away
+    /// same as dropping the session
+    pub fn end(self) {}
+}
+
+impl Drop for TerminalSession {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+// restores the terminal to its pre session state
+// does nothing if no session is active, so it is safe to call more than once
+fn restore() {
+    let state = ACTIVE.lock().unwrap_or_else(|e| e.into_inner()).take();
+    let Some(state) = state else {
+        return;
+    };
+
+    let mut writer = std::io::stdout().lock();
+    teardown(&state, &mut writer);
+    _ = writer.flush();
+    drop(writer);
+
+    if let Some(original) = state.original {
+        cooked_mode(original);
+    }
+}
+
+// restores the terminal from the panic hook
+// the panic may come from inside start, suspend or resume while this thread holds the ACTIVE lock,
+// and the panicking code may be holding the stdout lock; waiting on either would never return,
+// so a busy lock skips the restore and the bytes go straight to the stdout file descriptor
+fn restore_on_panic() {
+    let mut active = match ACTIVE.try_lock() {
+        Ok(active) => active,
+        Err(TryLockError::Poisoned(e)) => e.into_inner(),
+        Err(TryLockError::WouldBlock) => return,
+    };
+    // panics on other threads, e.g., a worker's, leave the session to its owner
+    let Some(state) = active.take_if(|state| state.thread == thread::current().id()) else {
+        return;
+    };
+    drop(active);
+
+    let mut bytes = vec![];
+    teardown(&state, &mut bytes);
+    let mut written = 0;
+    while written < bytes.len() {
+        let rest = &bytes[written..];
+        let n = unsafe { write(STDOUT_FILENO, rest.as_ptr(), rest.len()) };
+        if n <= 0 {
+            break;
+        }
+        written += n as usize;
+    }
+
+    if let Some(original) = state.original {
+        cooked_mode(original);
+    }
+}
+
+// writes the sequences that take the session modes down
+fn teardown(state: &SessionState, writer: &mut impl Write) {
+    if state.meta.job_control {
+        suspend::unlisten();
+    }
+
+    reset(state, writer);
+    if !state.suspended {
+        unapply(&state.meta, writer);
+    }
+    if state.meta.save_title {
+        pop_title(writer);
+    }
+}
+
+// takes back what renders changed outside of the session modes, and only that
+// the state is locked, the marks can't be taken through CursorStyle::apply
+fn reset(state: &SessionState, writer: &mut impl Write) {
+    if state.cursor {
+        _ = writer.write_all(CursorStyle::default().sequence().as_bytes());
+    }
+    if state.region {
+        _ = writer.write_all(RESET_REGION);
+    }
+}
+
+// notes whether the cursor style last written is other than the default
+// so that the session only resets the cursor when it ends if it needs to
+pub(crate) fn mark_cursor(changed: bool) {
+    if let Some(state) = ACTIVE.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        state.cursor = changed;
+    }
+}
+
+// notes whether an inline Term's scroll region is set
+pub(crate) fn mark_region(changed: bool) {
+    if let Some(state) = ACTIVE.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        state.region = changed;
+    }
+}
+
+// turns the session modes on
+fn apply(meta: &SessionMeta, writer: &mut impl Write) {
+    if meta.alternate_screen {
+        enter_alternate_screen(writer);
+    }
+    if meta.mouse {
+        enable_mouse_input(writer, meta.tracking);
+    }
+    if meta.bracketed_paste {
+        enable_bracketed_paste(writer);
+    }
+    if meta.kitty_keyboard != 0 {
+        enable_kitty_keyboard(writer, meta.kitty_keyboard);
+    }
+    if meta.keypad_mode {
+        enable_keypad_mode(writer);
+    }
+    if meta.focus_reporting {
+        enable_focus_reporting(writer);
+    }
+}
+
+// turns the session modes off, in the reverse order
+fn unapply(meta: &SessionMeta, writer: &mut impl Write) {
+    if meta.focus_reporting {
+        disable_focus_reporting(writer);
+    }
+    if meta.keypad_mode {
+        disable_keypad_mode(writer);
+    }
+    if meta.kitty_keyboard != 0 {
+        disable_kitty_keyboard(writer);
+    }
+    if meta.bracketed_paste {
+        disable_bracketed_paste(writer);
+    }
+    if meta.mouse {
+        disable_mouse_input(writer);
+    }
+    if meta.alternate_screen {
+        leave_alternate_screen(writer);
+    }
+}
+
+/// suspends the program the way ctrl+z does in a shell, returns once it is continued, e.g., by fg
+///
+/// the active session's modes are taken down and cooked mode restored before the program stops,
+/// so that the shell gets a usable terminal back; they are applied again when it continues.
+/// the screen is left as the shell had it and nothing redraws it on its own,
+/// call ComponentTree::redraw after this
+///
+/// # Examples
+/// ```
+/// // raw mode turns ISIG off, so ctrl+z only comes in as a key
+/// keymap.bind("C-z", Action::Suspend).unwrap();
+///
+/// if let Resolved::Action(Action::Suspend) = keymap.feed(ke) {
+///     suspend();
+///     tree.redraw(&mut writer);
+/// }
+/// ```
+pub fn suspend() {
+    take_down(&mut std::io::stdout().lock());
+    signals::stop();
+    resume();
+}
+
+// takes the active session's modes down for a suspension
+// does nothing if no session is active or if it is already suspended
+fn take_down(writer: &mut impl Write) {
+    let mut active = ACTIVE.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(state) = active.as_mut().filter(|state| !state.suspended) else {
+        return;
+    };
+
+    reset(state, writer);
+    // the terminal is back to its defaults, the program redraws on the resume anyway
+    [state.cursor, state.region] = [false, false];
+    unapply(&state.meta, writer);
+    _ = writer.flush();
+    if let Some(original) = state.original.clone() {
+        cooked_mode(original);
+    }
+    state.suspended = true;
+}
+
+// applies the session modes again after the program was continued
+pub(crate) fn resume() {
+    bring_up(&mut std::io::stdout().lock());
+}
+
+// raw mode is set again in any case, the shell may have changed the termios flags while the program was stopped
+fn bring_up(writer: &mut impl Write) {
+    let mut active = ACTIVE.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(state) = active.as_mut() else {
+        return;
+    };
+
+    if state.original.is_some() {
+        raw_mode();
+    }
+    if state.suspended {
+        apply(&state.meta, writer);
+        _ = writer.flush();
+        state.suspended = false;
+    }
+}
+
+// chains a terminal restoring hook in front of the current panic hook
+// so that the panic message is printed on the original screen in cooked mode
+//
+// the hook can't tell a panic that catch_unwind will catch from one that ends the program,
+// a caught panic on the session's thread restores the terminal all the same
+fn install_panic_hook() {
+    static HOOK: Once = Once::new();
+
+    HOOK.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            restore_on_panic();
+            previous(info);
+        }));
+    });
+}
+
+// the session is process wide, the tests that start one or mark it take turns
+#[cfg(test)]
+pub(crate) static SERIAL: Mutex<()> = Mutex::new(());
+
+#[cfg(test)]
+mod test_session {
+    use super::{bring_up, mark_region, restore_on_panic, take_down, SessionMeta, ACTIVE, SERIAL};
+    use crate::console::cursor::{CursorShape, CursorStyle};
+
+    fn active() -> bool {
+        ACTIVE.lock().unwrap_or_else(|e| e.into_inner()).is_some()
+    }
+
+    fn suspended() -> bool {
+        ACTIVE
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .is_some_and(|state| state.suspended)
+    }
+
+    #[test]
+    fn single_session() {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+
+        let session = SessionMeta::new().start().unwrap();
+        let err = SessionMeta::new().start().unwrap_err();
+        assert_eq!(err.to_string(), "a terminal session is already active");
+        assert!(active());
+
+        session.end();
+        assert!(!active());
+        SessionMeta::new().start().unwrap().end();
+        assert!(!active());
+    }
+
+    #[test]
+    fn panic_restore() {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+
+        let session = SessionMeta::new().start().unwrap();
+        // a panic while the lock is held, e.g., inside suspend, must not wait on it
+        {
+            let _held = ACTIVE.lock().unwrap();
+            restore_on_panic();
+        }
+        assert!(active());
+
+        // a panic on another thread leaves the session to its owner
+        std::thread::spawn(restore_on_panic).join().unwrap();
+        assert!(active());
+
+        restore_on_panic();
+        assert!(!active());
+        // dropping the session afterwards has nothing left to do
+        drop(session);
+        assert!(!active());
+    }
+
+    #[test]
+    fn suspend_resume() {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+
+        // no session, nothing to take down or bring back up
+        let mut bytes = vec![];
+        take_down(&mut bytes);
+        bring_up(&mut bytes);
+        assert!(bytes.is_empty());
+        assert!(!active());
+
+        let session = SessionMeta::new().bracketed_paste(true).start().unwrap();
+        take_down(&mut bytes);
+        assert!(suspended());
+        assert!(bytes.ends_with(b"\x1b[?2004l"));
+
+        // a second suspension has nothing left to take down
+        bytes.clear();
+        take_down(&mut bytes);
+        assert!(suspended());
+        assert!(bytes.is_empty());
+
+        bring_up(&mut bytes);
+        assert!(!suspended());
+        assert_eq!(bytes, b"\x1b[?2004h");
+
+        // a resume without a suspension, e.g., a lone SIGCONT, applies nothing twice
+        bytes.clear();
+        bring_up(&mut bytes);
+        assert!(bytes.is_empty());
+
+        session.end();
+        assert!(!active());
+    }
+
+    #[test]
+    fn resets_only_changes() {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+
+        // nothing was changed, nothing gets reset
+        let session = SessionMeta::new().start().unwrap();
+        let mut bytes = vec![];
+        take_down(&mut bytes);
+        bring_up(&mut bytes);
+        assert!(bytes.is_empty());
+
+        CursorStyle::new().shape(CursorShape::SteadyBar).apply(&mut bytes);
+        mark_region(true);
+        bytes.clear();
+        take_down(&mut bytes);
+        assert_eq!(bytes, b"\x1b[0 q\x1b[?25h\x1b[?6l\x1b[r");
+
+        // the suspension reset them, the resumed session has nothing to reset
+        bring_up(&mut bytes);
+        bytes.clear();
+        take_down(&mut bytes);
+        assert!(bytes.is_empty());
+        bring_up(&mut bytes);
+
+        // a style set back to the default needs no reset either
+        CursorStyle::new().visible(false).apply(&mut bytes);
+        CursorStyle::default().apply(&mut bytes);
+        bytes.clear();
+        take_down(&mut bytes);
+        assert!(bytes.is_empty());
+
+        session.end();
+    }
+}
@@ -0,0 +1,136 @@
+use std::sync::atomic::{AtomicI32, AtomicU64, Ordering};
+use std::sync::Once;
+
+// from /usr/include/signal.h
+extern "C" {
+    fn signal(__sig: i32, __handler: usize) -> usize;
+    fn raise(__sig: i32) -> i32;
+}
+
+// from /usr/include/unistd.h
+extern "C" {
+    fn pipe(__pipedes: *mut i32) -> i32;
+    fn read(__fd: i32, __buf: *mut u8, __nbytes: usize) -> isize;
+    fn write(__fd: i32, __buf: *const u8, __n: usize) -> isize;
+}
+
+// from /usr/include/fcntl.h
+extern "C" {
+    fn fcntl(__fd: i32, __cmd: i32, ...) -> i32;
+}
+
+// from /usr/include/bits/signum-generic.h
+const SIG_DFL: usize = 0;
+
+// from /usr/include/bits/signum-arch.h
+/// terminal window size changed
+pub const SIGWINCH: i32 = 28;
+/// stop request from the terminal, e.g., ctrl+z
+#[cfg(not(target_os = "macos"))]
+pub const SIGTSTP: i32 = 20;
+#[cfg(target_os = "macos")]
+pub const SIGTSTP: i32 = 18;
+/// the process was continued after a stop
+#[cfg(not(target_os = "macos"))]
+pub const SIGCONT: i32 = 18;
+#[cfg(target_os = "macos")]
+pub const SIGCONT: i32 = 19;
+
+// from /usr/include/asm-generic/fcntl.h
+const F_GETFL: i32 = 3;
+const F_SETFL: i32 = 4;
+#[cfg(not(target_os = "macos"))]
+const O_NONBLOCK: i32 = 0o4000;
+#[cfg(target_os = "macos")]
+const O_NONBLOCK: i32 = 0x0004;
+
+// the self pipe; the signal handler writes a byte to PIPE[1]
+// so that whoever is polling PIPE[0] wakes up
+static PIPE: [AtomicI32; 2] = [AtomicI32::new(-1), AtomicI32::new(-1)];
+
+// one bit per signal number, set by the handler and cleared by take()
+static PENDING: AtomicU64 = AtomicU64::new(0);
+
+// only async signal safe operations are allowed in here
+extern "C" fn on_signal(sig: i32) {
+    PENDING.fetch_or(1 << sig, Ordering::SeqCst);
+
+    let fd = PIPE[1].load(Ordering::SeqCst);
+    if fd >= 0 {
+        let byte = sig as u8;
+        _ = unsafe { write(fd, &byte, 1) };
+    }
+}
+
+fn open_pipe() {
+    static PIPE_INIT: Once = Once::new();
+
+    PIPE_INIT.call_once(|| {
+        let mut fds = [-1; 2];
+        if unsafe { pipe(fds.as_mut_ptr()) } != 0 {
+            return;
+        }
+
+        // neither the handler nor drain() should ever block on the pipe
+        for fd in fds {
+            unsafe {
+                let flags = fcntl(fd, F_GETFL);
+                fcntl(fd, F_SETFL, flags | O_NONBLOCK);
+            }
+        }
+
+        PIPE[0].store(fds[0], Ordering::SeqCst);
+        PIPE[1].store(fds[1], Ordering::SeqCst);
+    });
+}
+
+/// installs the self pipe handler for the given signal
+/// once installed, the signal wakes up any input read that is waiting on stdin
+pub fn listen(sig: i32) {
+    open_pipe();
+    unsafe { signal(sig, on_signal as usize) };
+}
+
+/// restores the default disposition of the given signal
+pub fn unlisten(sig: i32) {
+    unsafe { signal(sig, SIG_DFL) };
+}
+
+/// stops the process the way the default SIGTSTP disposition does
+/// returns once the process is continued
+pub fn stop() {
+    unsafe {
+        let previous = signal(SIGTSTP, SIG_DFL);
+        raise(SIGTSTP);
+        signal(SIGTSTP, previous);
+    }
+}
+
+/// returns whether the given signal was received since the last call, then clears it
+pub fn take(sig: i32) -> bool {
+    let bit = 1 << sig;
+
+    PENDING.fetch_and(!bit, Ordering::SeqCst) & bit != 0
+}
+
+/// returns whether the given signal was received since the last take, without clearing it
+pub fn pending(sig: i32) -> bool {
+    PENDING.load(Ordering::SeqCst) & (1 << sig) != 0
+}
+
+// returns the read end of the self pipe
+// or -1 if no signal is being listened to
+pub(crate) fn wake_fd() -> i32 {
+    PIPE[0].load(Ordering::SeqCst)
+}
+
+// empties the self pipe after a wake up
+pub(crate) fn drain() {
+    let fd = wake_fd();
+    if fd < 0 {
+        return;
+    }
+
+    let mut buf = [0u8; 64];
+    while unsafe { read(fd, buf.as_mut_ptr(), buf.len()) } > 0 {}
+}
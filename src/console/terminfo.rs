@@ -0,0 +1,664 @@
+use std::collections::HashMap;
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+
+// compiled terminfo format, see term(5)
+// the legacy format stores numbers on 2 bytes, the extended number format on 4
+const MAGIC_LEGACY: i16 = 0o432;
+const MAGIC_EXTENDED: i16 = 0o1036;
+
+// the standard capabilities the library has a use for, with their index in the compiled entry
+// the index order comes from ncurses' term.h
+const BOOLEANS: &[(&str, usize)] = &[("am", 1), ("hs", 9), ("bce", 28)];
+const NUMBERS: &[(&str, usize)] = &[("cols", 0), ("lines", 2), ("colors", 13)];
+const STRINGS: &[(&str, usize)] = &[
+    ("bel", 1),
+    ("cr", 2),
+    ("csr", 3),
+    ("clear", 5),
+    ("el", 6),
+    ("ed", 7),
+    ("hpa", 8),
+    ("cup", 10),
+    ("cud1", 11),
+    ("home", 12),
+    ("civis", 13),
+    ("cub1", 14),
+    ("cnorm", 16),
+    ("cuf1", 17),
+    ("cuu1", 19),
+    ("cvvis", 20),
+    ("dch1", 21),
+    ("dl1", 22),
+    ("smacs", 25),
+    ("blink", 26),
+    ("bold", 27),
+    ("smcup", 28),
+    ("dim", 30),
+    ("invis", 32),
+    ("rev", 34),
+    ("smso", 35),
+    ("smul", 36),
+    ("ech", 37),
+    ("rmacs", 38),
+    ("sgr0", 39),
+    ("rmcup", 40),
+    ("rmso", 43),
+    ("rmul", 44),
+    ("flash", 45),
+    ("fsl", 47),
+    ("ich1", 52),
+    ("il1", 53),
+    ("rmkx", 88),
+    ("smkx", 89),
+    ("dch", 105),
+    ("dl", 106),
+    ("cud", 107),
+    ("ich", 108),
+    ("il", 110),
+    ("cub", 111),
+    ("cuf", 112),
+    ("cuu", 114),
+    ("rc", 126),
+    ("vpa", 127),
+    ("sc", 128),
+    ("ind", 129),
+    ("ri", 130),
+    ("sgr", 131),
+    ("tsl", 135),
+    ("smam", 151),
+    ("rmam", 152),
+    ("op", 297),
+    ("sitm", 311),
+    ("ritm", 321),
+    ("setaf", 359),
+    ("setab", 360),
+];
+
+/// a terminal's entry from the terminfo database
+///
+/// holds the escape strings the terminal really understands,
+/// for when the xterm ones the library writes by default are wrong, e.g., on the linux console
+///
+/// # Examples
+/// ```
+/// let ti = Terminfo::from_env().unwrap();
+/// if let Some(cup) = ti.expand("cup", &[row, col]) {
+///     _ = writer.write(&cup);
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Terminfo {
+    /// the names of the terminal, e.g., ["xterm-256color", "xterm with 256 colors"]
+    pub names: Vec<String>,
+    booleans: Vec<bool>,
+    numbers: Vec<i32>,
+    strings: Vec<Option<Vec<u8>>>,
+    /// user defined capabilities, e.g., Smulx or RGB
+    extended: Extended,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Extended {
+    booleans: HashMap<String, bool>,
+    numbers: HashMap<String, i32>,
+    strings: HashMap<String, Vec<u8>>,
+}
+
+impl Terminfo {
+    /// loads the entry of the terminal named by the TERM environment variable
+    ///
+    /// # Errors
+    ///
+    /// returns an error if TERM is not set, or if its entry can't be found or parsed
+    pub fn from_env() -> Result<Self, Error> {
+        let term = std::env::var("TERM").map_err(|e| Error::new(ErrorKind::NotFound, e))?;
+
+        Self::load(&term)
+    }
+
+    /// loads the entry of the given terminal from the terminfo database
+    /// searching $TERMINFO, ~/.terminfo, $TERMINFO_DIRS, /etc/terminfo, /lib/terminfo and /usr/share/terminfo
+    ///
+    /// # Errors
+    ///
+    /// returns an error if the entry can't be found or parsed
+    pub fn load(term: &str) -> Result<Self, Error> {
+        let path = find(&search_dirs(), term).ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!("no terminfo entry for {}", term),
+            )
+        })?;
+
+        Self::parse(&std::fs::read(path)?)
+    }
+
+    /// parses a compiled terminfo entry, in the legacy or the extended number format
+    ///
+    /// # Errors
+    ///
+    /// returns an error if the bytes are not a valid compiled entry
+    pub fn parse(bytes: &[u8]) -> Result<Self, Error> {
+        parse(bytes).ok_or_else(|| Error::new(ErrorKind::InvalidData, "malformed terminfo entry"))
+    }
+
+    /// returns the value of the given boolean capability, e.g., "am" or the extended "AX"
+    pub fn flag(&self, name: &str) -> bool {
+        match lookup(BOOLEANS, name) {
+            Some(idx) => self.booleans.get(idx).copied().unwrap_or(false),
+            None => self.extended.booleans.get(name).copied().unwrap_or(false),
+        }
+    }
+
+    /// returns the value of the given numeric capability, e.g., "colors"
+    pub fn number(&self, name: &str) -> Option<i32> {
+        match lookup(NUMBERS, name) {
+            Some(idx) => self.numbers.get(idx).copied().filter(|n| *n >= 0),
+            None => self.extended.numbers.get(name).copied(),
+        }
+    }
+
+    /// returns the raw value of the given string capability, e.g., "smcup" or the extended "Smulx"
+    /// the parameters and padding are left as is, see expand
+    pub fn string(&self, name: &str) -> Option<&[u8]> {
+        match lookup(STRINGS, name) {
+            Some(idx) => self.strings.get(idx)?.as_deref(),
+            None => self.extended.strings.get(name).map(|s| s.as_slice()),
+        }
+    }
+
+    /// returns the given string capability with its parameters filled in
+    /// e.g., expand("cup", &[row, col]) with 0 based row and col
+    pub fn expand(&self, name: &str, params: &[i32]) -> Option<Vec<u8>> {
+        Some(tparm(self.string(name)?, params))
+    }
+}
+
+fn lookup(caps: &[(&str, usize)], name: &str) -> Option<usize> {
+    caps.iter().find(|(n, _)| *n == name).map(|(_, idx)| *idx)
+}
+
+fn search_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![];
+    if let Ok(dir) = std::env::var("TERMINFO") {
+        dirs.push(PathBuf::from(dir));
+    }
+    if let Ok(home) = std::env::var("HOME") {
+        dirs.push(Path::new(&home).join(".terminfo"));
+    }
+    let defaults = ["/etc/terminfo", "/lib/terminfo", "/usr/share/terminfo"];
+    match std::env::var("TERMINFO_DIRS") {
+        // an empty entry stands for the default directories
+        Ok(list) => {
+            for dir in list.split(':') {
+                match dir {
+                    "" => dirs.extend(defaults.iter().map(PathBuf::from)),
+                    dir => dirs.push(PathBuf::from(dir)),
+                }
+            }
+        }
+        Err(_) => dirs.extend(defaults.iter().map(PathBuf::from)),
+    }
+
+    dirs
+}
+
+// entries live under a directory named after their first letter, e.g., x/xterm
+// or after its hex code on case insensitive file systems, e.g., 78/xterm
+fn find(dirs: &[PathBuf], term: &str) -> Option<PathBuf> {
+    let first = term.chars().next()?;
+    if term.contains('/') || term.starts_with('.') {
+        return None;
+    }
+
+    dirs.iter()
+        .flat_map(|dir| {
+            [
+                dir.join(first.to_string()).join(term),
+                dir.join(format!("{:x}", first as u32)).join(term),
+            ]
+        })
+        .find(|path| path.is_file())
+}
+
+// reads the little endian i16 at the given byte index
+fn i16_at(bytes: &[u8], idx: usize) -> Option<i16> {
+    Some(i16::from_le_bytes([*bytes.get(idx)?, *bytes.get(idx + 1)?]))
+}
+
+fn i32_at(bytes: &[u8], idx: usize) -> Option<i32> {
+    Some(i32::from_le_bytes(
+        bytes.get(idx..idx + 4)?.try_into().ok()?,
+    ))
+}
+
+fn count(n: i16) -> Option<usize> {
+    usize::try_from(n).ok()
+}
+
+// the NUL terminated string at the given offset of the table, None for a negative offset
+fn string_at(table: &[u8], offset: i16) -> Option<&[u8]> {
+    let rest = table.get(count(offset)?..)?;
+    let end = rest.iter().position(|b| *b == 0)?;
+
+    Some(&rest[..end])
+}
+
+fn parse(bytes: &[u8]) -> Option<Terminfo> {
+    let number_size = match i16_at(bytes, 0)? {
+        MAGIC_LEGACY => 2,
+        MAGIC_EXTENDED => 4,
+        _ => return None,
+    };
+    let names_size = count(i16_at(bytes, 2)?)?;
+    let bool_count = count(i16_at(bytes, 4)?)?;
+    let num_count = count(i16_at(bytes, 6)?)?;
+    let str_count = count(i16_at(bytes, 8)?)?;
+    let table_size = count(i16_at(bytes, 10)?)?;
+
+    let mut idx = 12;
+    let names = bytes.get(idx..idx + names_size)?;
+    let names = String::from_utf8_lossy(names.strip_suffix(&[0]).unwrap_or(names))
+        .split('|')
+        .map(String::from)
+        .collect();
+    idx += names_size;
+
+    let booleans = bytes
+        .get(idx..idx + bool_count)?
+        .iter()
+        .map(|b| *b == 1)
+        .collect();
+    idx += bool_count;
+    // numbers start on an even byte
+    idx += idx % 2;
+
+    let number = |at: usize| match number_size {
+        2 => i16_at(bytes, at).map(i32::from),
+        _ => i32_at(bytes, at),
+    };
+    let numbers = (0..num_count)
+        .map(|n| number(idx + n * number_size))
+        .collect::<Option<Vec<i32>>>()?;
+    idx += num_count * number_size;
+
+    let offsets = (0..str_count)
+        .map(|n| i16_at(bytes, idx + n * 2))
+        .collect::<Option<Vec<i16>>>()?;
+    idx += str_count * 2;
+    let table = bytes.get(idx..idx + table_size)?;
+    let strings = offsets
+        .iter()
+        .map(|offset| string_at(table, *offset).map(|s| s.to_vec()))
+        .collect();
+    idx += table_size;
+    idx += idx % 2;
+
+    // the extended capabilities are optional
+    let extended = match bytes.len() > idx {
+        true => parse_extended(&bytes[idx..], number_size)?,
+        false => Extended::default(),
+    };
+
+    Some(Terminfo {
+        names,
+        booleans,
+        numbers,
+        strings,
+        extended,
+    })
+}
+
+// the extended capabilities section; a header, the values, then the value strings and the names
+fn parse_extended(bytes: &[u8], number_size: usize) -> Option<Extended> {
+    let bool_count = count(i16_at(bytes, 0)?)?;
+    let num_count = count(i16_at(bytes, 2)?)?;
+    let str_count = count(i16_at(bytes, 4)?)?;
+    let table_size = count(i16_at(bytes, 8)?)?;
+
+    let mut idx = 10;
+    let booleans = bytes.get(idx..idx + bool_count)?.to_vec();
+    idx += bool_count;
+    idx += idx % 2;
+
+    let numbers = (0..num_count)
+        .map(|n| match number_size {
+            2 => i16_at(bytes, idx + n * 2).map(i32::from),
+            _ => i32_at(bytes, idx + n * 4),
+        })
+        .collect::<Option<Vec<i32>>>()?;
+    idx += num_count * number_size;
+
+    let name_count = bool_count + num_count + str_count;
+    let offsets = (0..str_count + name_count)
+        .map(|n| i16_at(bytes, idx + n * 2))
+        .collect::<Option<Vec<i16>>>()?;
+    idx += offsets.len() * 2;
+    let table = bytes.get(idx..idx + table_size)?;
+
+    // the names come right after the last value string, their offsets start from there
+    let values = &offsets[..str_count];
+    let names_start = values
+        .iter()
+        .filter_map(|offset| Some(count(*offset)? + string_at(table, *offset)?.len() + 1))
+        .max()
+        .unwrap_or(0);
+    let names = table.get(names_start..)?;
+    let names = offsets[str_count..]
+        .iter()
+        .map(|offset| string_at(names, *offset).map(|s| String::from_utf8_lossy(s).into_owned()))
+        .collect::<Option<Vec<String>>>()?;
+
+    let mut extended = Extended::default();
+    let (bool_names, rest) = names.split_at(bool_count);
+    let (num_names, str_names) = rest.split_at(num_count);
+    for (name, value) in bool_names.iter().zip(booleans) {
+        extended.booleans.insert(name.clone(), value == 1);
+    }
+    for (name, value) in num_names.iter().zip(numbers) {
+        if value >= 0 {
+            extended.numbers.insert(name.clone(), value);
+        }
+    }
+    for (name, offset) in str_names.iter().zip(values) {
+        if let Some(value) = string_at(table, *offset) {
+            extended.strings.insert(name.clone(), value.to_vec());
+        }
+    }
+
+    Some(extended)
+}
+
+/// fills in the parameters of a terminfo string capability, the way tparm(3) does
+/// e.g., \E[%i%p1%d;%p2%dH with [4, 9] gives \E[5;10H
+///
+/// supports the whole parameter language but string parameters;
+/// the $<..> padding is dropped, terminals don't need it anymore
+pub fn tparm(cap: &[u8], params: &[i32]) -> Vec<u8> {
+    let mut params = {
+        let mut p = [0; 9];
+        for (slot, param) in p.iter_mut().zip(params) {
+            *slot = *param;
+        }
+        p
+    };
+    let mut out = vec![];
+    let mut stack: Vec<i32> = vec![];
+    let mut dynamic = [0; 26];
+    let mut fixed = [0; 26];
+
+    let mut idx = 0;
+    while idx < cap.len() {
+        let b = cap[idx];
+        idx += 1;
+
+        if b == b'$' && cap.get(idx) == Some(&b'<') {
+            match cap[idx..].iter().position(|b| *b == b'>') {
+                Some(end) => idx += end + 1,
+                None => out.push(b),
+            }
+            continue;
+        }
+        if b != b'%' {
+            out.push(b);
+            continue;
+        }
+
+        let Some(&op) = cap.get(idx) else {
+            break;
+        };
+        idx += 1;
+        let mut pop = || stack.pop().unwrap_or(0);
+
+        match op {
+            b'%' => out.push(b'%'),
+            b'c' => out.push(pop() as u8),
+            b'p' => {
+                let n = cap.get(idx).map_or(0, |d| d.wrapping_sub(b'1') as usize);
+                stack.push(params.get(n).copied().unwrap_or(0));
+                idx += 1;
+            }
+            b'P' | b'g' => {
+                let var = cap.get(idx).copied().unwrap_or(0);
+                idx += 1;
+                let slot = match var {
+                    b'a'..=b'z' => &mut dynamic[(var - b'a') as usize],
+                    b'A'..=b'Z' => &mut fixed[(var - b'A') as usize],
+                    _ => continue,
+                };
+                match op {
+                    b'P' => *slot = stack.pop().unwrap_or(0),
+                    _ => stack.push(*slot),
+                }
+            }
+            b'\'' => {
+                stack.push(cap.get(idx).copied().unwrap_or(0) as i32);
+                idx += 2;
+            }
+            b'{' => {
+                let end = cap[idx..].iter().position(|b| *b == b'}').unwrap_or(0);
+                let n = std::str::from_utf8(&cap[idx..idx + end]).ok();
+                stack.push(n.and_then(|n| n.parse().ok()).unwrap_or(0));
+                idx += end + 1;
+            }
+            b'l' => {
+                // string lengths, only numbers live on this stack
+                pop();
+                stack.push(0);
+            }
+            b'+' | b'-' | b'*' | b'/' | b'm' | b'&' | b'|' | b'^' | b'=' | b'>' | b'<' | b'A'
+            | b'O' => {
+                let rhs = pop();
+                let lhs = pop();
+                stack.push(match op {
+                    b'+' => lhs.wrapping_add(rhs),
+                    b'-' => lhs.wrapping_sub(rhs),
+                    b'*' => lhs.wrapping_mul(rhs),
+                    b'/' => lhs.checked_div(rhs).unwrap_or(0),
+                    b'm' => lhs.checked_rem(rhs).unwrap_or(0),
+                    b'&' => lhs & rhs,
+                    b'|' => lhs | rhs,
+                    b'^' => lhs ^ rhs,
+                    b'=' => (lhs == rhs) as i32,
+                    b'>' => (lhs > rhs) as i32,
+                    b'<' => (lhs < rhs) as i32,
+                    b'A' => (lhs != 0 && rhs != 0) as i32,
+                    _ => (lhs != 0 || rhs != 0) as i32,
+                });
+            }
+            b'!' => {
+                let v = pop();
+                stack.push((v == 0) as i32);
+            }
+            b'~' => {
+                let v = pop();
+                stack.push(!v);
+            }
+            b'i' => {
+                params[0] += 1;
+                params[1] += 1;
+            }
+            b'?' | b';' => (),
+            // a false condition jumps to its else or to the end of the if
+            b't' => {
+                if pop() == 0 {
+                    idx = skip(cap, idx, true);
+                }
+            }
+            // the then part ran, the else part gets skipped
+            b'e' => idx = skip(cap, idx, false),
+            _ => {
+                // %[[:]flags][width[.precision]][doxXs]
+                let start = idx - 1;
+                let end = match cap[start..]
+                    .iter()
+                    .position(|b| matches!(b, b'd' | b'o' | b'x' | b'X' | b's'))
+                {
+                    Some(end) => start + end,
+                    None => break,
+                };
+                let spec = &cap[start..end];
+                let v = pop();
+                out.extend(format_number(v, spec, cap[end]));
+                idx = end + 1;
+            }
+        }
+    }
+
+    out
+}
+
+// returns the index right after the matching %e (if else_too) or %; of the current if
+fn skip(cap: &[u8], mut idx: usize, else_too: bool) -> usize {
+    let mut depth = 0;
+    while idx + 1 < cap.len() {
+        if cap[idx] != b'%' {
+            idx += 1;
+            continue;
+        }
+        match cap[idx + 1] {
+            b'?' => depth += 1,
+            b';' if depth == 0 => return idx + 2,
+            b';' => depth -= 1,
+            b'e' if depth == 0 && else_too => return idx + 2,
+            _ => (),
+        }
+        idx += 2;
+    }
+
+    cap.len()
+}
+
+// printf like formatting of one number
+fn format_number(v: i32, spec: &[u8], conv: u8) -> Vec<u8> {
+    let spec = spec.strip_prefix(b":").unwrap_or(spec);
+    let flags_end = spec
+        .iter()
+        .position(|b| !matches!(b, b'-' | b'+' | b'#' | b' ' | b'0'))
+        .unwrap_or(spec.len());
+    let (flags, size) = spec.split_at(flags_end);
+    let size = String::from_utf8_lossy(size);
+    let (width, precision) = match size.split_once('.') {
+        Some((w, p)) => (w.parse().unwrap_or(0), p.parse().ok()),
+        None => (size.parse().unwrap_or(0), None),
+    };
+
+    let mut digits = match conv {
+        b'o' => format!("{:o}", v.unsigned_abs()),
+        b'x' => format!("{:x}", v.unsigned_abs()),
+        b'X' => format!("{:X}", v.unsigned_abs()),
+        _ => v.unsigned_abs().to_string(),
+    };
+    if let Some(precision) = precision {
+        while digits.len() < precision {
+            digits.insert(0, '0');
+        }
+    }
+
+    let prefix = match conv {
+        _ if v < 0 => "-",
+        b'd' | b's' if flags.contains(&b'+') => "+",
+        b'd' | b's' if flags.contains(&b' ') => " ",
+        b'o' if flags.contains(&b'#') && !digits.starts_with('0') => "0",
+        b'x' if flags.contains(&b'#') && v != 0 => "0x",
+        b'X' if flags.contains(&b'#') && v != 0 => "0X",
+        _ => "",
+    };
+    let s = format!("{}{}", prefix, digits);
+    let s = match (flags.contains(&b'-'), flags.contains(&b'0')) {
+        (true, _) => format!("{:<width$}", s, width = width),
+        (false, true) => {
+            let zeros = "0".repeat(width.saturating_sub(s.len()));
+            format!("{}{}{}", prefix, zeros, digits)
+        }
+        (false, false) => format!("{:>width$}", s, width = width),
+    };
+
+    s.into_bytes()
+}
+
+#[cfg(test)]
+mod test_terminfo {
+    use super::{find, tparm, Terminfo};
+
+    const SCREEN: &[u8] = include_bytes!("terminfo/screen");
+    const XTERM: &[u8] = include_bytes!("terminfo/xterm-256color");
+
+    #[test]
+    fn legacy() {
+        let ti = Terminfo::parse(SCREEN).unwrap();
+        assert_eq!(ti.names[0], "screen");
+        assert!(ti.flag("am"));
+        assert!(!ti.flag("bce"));
+        assert_eq!(ti.number("colors"), Some(8));
+        assert_eq!(ti.string("smcup"), Some(&b"\x1b[?1049h"[..]));
+        assert_eq!(ti.expand("setaf", &[3]).unwrap(), b"\x1b[33m");
+        assert_eq!(ti.expand("cup", &[4, 9]).unwrap(), b"\x1b[5;10H");
+        assert!(ti.string("sitm").is_none());
+    }
+
+    #[test]
+    fn extended_numbers() {
+        let ti = Terminfo::parse(XTERM).unwrap();
+        assert_eq!(ti.names[0], "xterm-256color");
+        assert_eq!(ti.number("colors"), Some(256));
+        assert_eq!(ti.string("sgr0"), Some(&b"\x1b(B\x1b[m"[..]));
+        assert_eq!(ti.expand("ech", &[12]).unwrap(), b"\x1b[12X");
+
+        // setaf picks a different sequence for each color range
+        assert_eq!(ti.expand("setaf", &[1]).unwrap(), b"\x1b[31m");
+        assert_eq!(ti.expand("setaf", &[9]).unwrap(), b"\x1b[91m");
+        assert_eq!(ti.expand("setaf", &[200]).unwrap(), b"\x1b[38;5;200m");
+
+        // user defined capabilities
+        assert!(ti.flag("AX"));
+        assert_eq!(ti.string("E3"), Some(&b"\x1b[3J"[..]));
+        assert_eq!(ti.string("kDC3"), Some(&b"\x1b[3;3~"[..]));
+        assert_eq!(ti.expand("XM", &[1]).unwrap(), b"\x1b[?1006;1000h");
+        assert_eq!(ti.expand("XM", &[0]).unwrap(), b"\x1b[?1006;1000l");
+    }
+
+    #[test]
+    fn malformed() {
+        assert!(Terminfo::parse(b"").is_err());
+        assert!(Terminfo::parse(&SCREEN[..100]).is_err());
+        assert!(Terminfo::parse(&XTERM[..XTERM.len() - 10]).is_err());
+        let mut bytes = SCREEN.to_vec();
+        bytes[0] = 0;
+        assert!(Terminfo::parse(&bytes).is_err());
+    }
+
+    #[test]
+    fn parameters() {
+        assert_eq!(tparm(b"%p1%02d:%p2%x:%p2%#X", &[5, 255]), b"05:ff:0XFF");
+        assert_eq!(tparm(b"%p1%:-4d|", &[7]), b"7   |");
+        assert_eq!(tparm(b"%p1%c%'A'%c", &[66]), b"BA");
+        assert_eq!(tparm(b"%p1%Pa%ga%ga%*%d", &[6]), b"36");
+        assert_eq!(tparm(b"%{10}%p1%-%d", &[3]), b"7");
+        assert_eq!(
+            tparm(b"%?%p1%{1}%=%tone%e%p1%{2}%=%ttwo%eother%;", &[2]),
+            b"two"
+        );
+        assert_eq!(tparm(b"%?%p1%t%?%p2%ta%eb%;%ec%;", &[1, 0]), b"b");
+        assert_eq!(tparm(b"\x1b[H$<5/>x%%", &[]), b"\x1b[Hx%");
+        // nothing breaks on garbage
+        _ = tparm(b"%p9%{%'%?%t%e%", &[]);
+        _ = tparm(b"%{1}%{0}%/%d", &[]);
+    }
+
+    #[test]
+    fn search() {
+        let dir = std::env::temp_dir().join(format!("ragout-terminfo-{}", std::process::id()));
+        let entry = dir.join("x").join("xterm-256color");
+        std::fs::create_dir_all(entry.parent().unwrap()).unwrap();
+        std::fs::write(&entry, XTERM).unwrap();
+
+        let dirs = [dir.join("missing"), dir.clone()];
+        assert_eq!(find(&dirs, "xterm-256color"), Some(entry));
+        assert!(find(&dirs, "screen").is_none());
+        assert!(find(&dirs, "../x/xterm-256color").is_none());
+
+        _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,53 @@
+use std::io::Write;
+
+/// sets the terminal window title (OSC 2)
+pub fn set_title(writer: &mut impl Write, title: &str) {
+    _ = writer.write(&osc(2, title));
+}
+
+/// sets the terminal icon name (OSC 1), the title of the minimized window or of the tab on some terminals
+pub fn set_icon_name(writer: &mut impl Write, name: &str) {
+    _ = writer.write(&osc(1, name));
+}
+
+/// sets both the window title and the icon name (OSC 0)
+pub fn set_title_and_icon_name(writer: &mut impl Write, title: &str) {
+    _ = writer.write(&osc(0, title));
+}
+
+/// saves the current window title and icon name on the terminal's title stack
+/// pop_title restores them
+pub fn push_title(writer: &mut impl Write) {
+    _ = writer.write(b"\x1b[22;0t");
+}
+
+/// restores the window title and icon name last saved by push_title
+pub fn pop_title(writer: &mut impl Write) {
+    _ = writer.write(b"\x1b[23;0t");
+}
+
+// control characters would end the sequence early, they are left out
+fn osc(code: u8, text: &str) -> Vec<u8> {
+    let text = text.chars().filter(|c| !c.is_control()).collect::<String>();
+
+    format!("\x1b]{};{}\x1b\\", code, text).into_bytes()
+}
+
+#[cfg(test)]
+mod test_title {
+    use super::{osc, set_title, set_title_and_icon_name};
+
+    #[test]
+    fn sequence() {
+        assert_eq!(osc(2, "ragout ✓"), "\x1b]2;ragout ✓\x1b\\".as_bytes());
+        assert_eq!(osc(0, "a\x07b\x1b\\c\n"), b"\x1b]0;ab\\c\x1b\\");
+    }
+
+    #[test]
+    fn written() {
+        let mut bytes = vec![];
+        set_title(&mut bytes, "ragout");
+        set_title_and_icon_name(&mut bytes, "r");
+        assert_eq!(bytes, b"\x1b]2;ragout\x1b\\\x1b]0;r\x1b\\");
+    }
+}
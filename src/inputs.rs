@@ -4,78 +4,270 @@ pub mod keyboard;
 pub mod mouse;
 /// window user input events, such as a resize or a focus change
 pub mod window;
+/// background input reading thread that sends input events through a channel
+pub mod reader;
+/// incremental decoding of raw input bytes into input events
+pub mod parser;
+/// key binding notation, e.g., "C-x C-s", and chord to action maps
+pub mod keymap;
+/// raw input recording into a line based file and its replay
+pub mod recording;
 
-use keyboard::{decode_ki, decode_ki_kai, Char, KbdEvent, PasteEvent};
-use mouse::{decode_mi, MouseEvent};
-use window::WindowEvent;
+use crate::console::clipboard::ClipboardEvent;
+use keyboard::{KbdEvent, PasteEvent};
+use mouse::MouseEvent;
+use window::{resize, suspend, WindowEvent};
 
+pub use keymap::{Keymap, Resolved};
+pub use parser::InputParser;
+pub use recording::{Recorder, Replay};
+pub use reader::{EventReader, EventSender};
+
+use std::any::Any;
+use std::cell::Cell;
 use std::io::BufRead;
+use std::io::ErrorKind;
 use std::io::StdinLock;
-use std::time::SystemTime;
+use std::time::{Duration, Instant, SystemTime};
 
 use crate::components::Term;
-use crate::console::winsize::winsize;
+use crate::console::{poll, signals, winsize::winsize};
+
+// from /usr/include/unistd.h
+const STDIN_FILENO: i32 = 0;
 
 /// reads the keyboard input event bytes
-pub fn read<'a>(reader: &'a mut StdinLock, buffer: &'a mut Vec<u8>) -> &'a mut Vec<u8> {
-    // TODO: non blocking reads
+///
+/// blocks until some input arrives
+/// if a signal is being listened to (e.g., window::resize::listen()),
+/// the read wakes up on that signal and returns an empty buffer
+///
+/// a closed stdin also returns an empty buffer, right away on every read;
+/// event then returns an Interaction::Closed
+pub fn read<'a, S: InputSource>(reader: &'a mut S, buffer: &'a mut Vec<u8>) -> &'a mut Vec<u8> {
     buffer.clear();
 
-    let buf = reader.fill_buf().unwrap();
-    buffer.extend_from_slice(buf);
-
-    let n = buf.len();
-    reader.consume(n);
+    // with no timeout, wait only returns once something is ready
+    match reader.wait(None) {
+        Some(Ready::Input) => fill_or_close(reader, buffer),
+        _ => {
+            CLOSED.set(false);
+            REPLAYED.set(None);
+            reader.drain();
+        }
+    }
 
     buffer
 }
 
+thread_local! {
+    // whether the last read of this thread found its source closed
+    // stdin at end of file or after an error, a finished Replay
+    static CLOSED: Cell<bool> = const { Cell::new(false) };
+    // the signal a Replay played back on the last read of this thread, in place of a real one
+    static REPLAYED: Cell<Option<recording::Signal>> = const { Cell::new(None) };
+}
+
+// input that is ready but reads no bytes means the source is closed
+fn fill_or_close<S: InputSource>(reader: &mut S, buffer: &mut Vec<u8>) {
+    reader.fill(buffer);
+    CLOSED.set(buffer.is_empty());
+}
+
+/// reads the keyboard input event bytes, waiting for them for at most the given timeout
+///
+/// returns None if nothing arrived before the timeout expired
+/// just like read, an empty buffer means that a signal woke up the read
+///
+/// # Examples
+/// ```
+/// loop {
+///     // redraw the clock every second even if the user is idle
+///     if let Some(input) = read_timeout(&mut reader, &mut buf, Duration::from_secs(1)) {
+///         let ie = event(input, &mut ws);
+///     }
+///     clock.render(&mut writer);
+/// }
+/// ```
+pub fn read_timeout<'a, S: InputSource>(
+    reader: &'a mut S,
+    buffer: &'a mut Vec<u8>,
+    timeout: Duration,
+) -> Option<&'a mut Vec<u8>> {
+    buffer.clear();
+
+    match reader.wait(Some(timeout))? {
+        Ready::Input => fill_or_close(reader, buffer),
+        Ready::Signal => {
+            CLOSED.set(false);
+            REPLAYED.set(None);
+            reader.drain();
+        }
+    }
+
+    Some(buffer)
+}
+
+/// reads the keyboard input event bytes if there are any, without blocking
+///
+/// returns None if no input was waiting to be read
+pub fn try_read<'a, S: InputSource>(
+    reader: &'a mut S,
+    buffer: &'a mut Vec<u8>,
+) -> Option<&'a mut Vec<u8>> {
+    read_timeout(reader, buffer, Duration::ZERO)
+}
+
+/// what woke up a wait
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Ready {
+    /// some input bytes are waiting to be read
+    Input,
+    /// a signal arrived, e.g., a window resize
+    Signal,
+}
+
+/// where read, read_timeout and try_read get their input bytes from
+/// implemented by the stdin lock, and by Replay and Recorder to replay and record input
+pub trait InputSource {
+    /// waits for at most the given timeout, or forever if there is none, until input or a signal is ready
+    /// returns None if the timeout expired
+    fn wait(&mut self, timeout: Option<Duration>) -> Option<Ready>;
+
+    /// moves the waiting input bytes into the buffer
+    fn fill(&mut self, buffer: &mut Vec<u8>);
+
+    /// consumes the signal that woke up the wait
+    fn drain(&mut self);
+}
+
+impl InputSource for StdinLock<'_> {
+    fn wait(&mut self, timeout: Option<Duration>) -> Option<Ready> {
+        wait(timeout)
+    }
+
+    fn fill(&mut self, buffer: &mut Vec<u8>) {
+        fill(self, buffer)
+    }
+
+    fn drain(&mut self) {
+        signals::drain()
+    }
+}
+
+// polls stdin and the signals self pipe until one of them is ready or the timeout expires
+// no timeout means waiting forever
+fn wait(timeout: Option<Duration>) -> Option<Ready> {
+    let deadline = timeout.map(|t| Instant::now() + t);
+    let wake = signals::wake_fd();
+    let fds = if wake >= 0 {
+        vec![STDIN_FILENO, wake]
+    } else {
+        vec![STDIN_FILENO]
+    };
+
+    loop {
+        let ms = match deadline {
+            None => -1,
+            Some(deadline) => {
+                let left = deadline.saturating_duration_since(Instant::now());
+                // round up so that sub millisecond timeouts don't busy loop
+                left.as_micros().div_ceil(1000).min(i32::MAX as u128) as i32
+            }
+        };
+
+        let ready = poll::readable(&fds, ms);
+        // signals take priority, the pending input is picked up by the next read
+        if ready.get(1) == Some(&true) {
+            return Some(Ready::Signal);
+        } else if ready[0] {
+            return Some(Ready::Input);
+        }
+
+        // nothing is ready, either the timeout expired or a signal interrupted the poll
+        // in the latter case the self pipe becomes readable on the next iteration
+        if ms == 0 {
+            return None;
+        }
+    }
+}
+
+// moves the bytes waiting in stdin into the buffer
+// a paste bigger than what one read gets is put back together by the InputParser
+fn fill(reader: &mut StdinLock, buffer: &mut Vec<u8>) {
+    loop {
+        match reader.fill_buf() {
+            Ok(buf) => {
+                buffer.extend_from_slice(buf);
+                let n = buf.len();
+                reader.consume(n);
+            }
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            // e.g., EIO once the terminal is gone, taken as a closed stdin
+            Err(_) => (),
+        }
+
+        break;
+    }
+}
+
 /// resolves the read input bytes to an InputEvent struct instance
+///
+/// an empty bytes slice means that read was woken up by a signal rather than by some input,
+/// or that stdin is closed, which gets reported as an Interaction::Closed
+/// window resizes are only reported after window::resize::listen() has been called
+/// and resumes after a suspension after window::suspend::listen()
+/// a signal that has no event to report gives an empty Interaction::Unknown
+///
+/// only the first event of the bytes is returned,
+/// use an InputParser to get all of them and to handle sequences split over many reads
 pub fn event(bytes: &[u8], ws: &mut winsize) -> InputEvent {
-    // FIXME: this only gets triggered on this blocking event fn
-    // it needs to be triggered on the actual resize event
-    if ws.resized() {
-        return InputEvent {
+    if bytes.is_empty() {
+        return woken(ws).unwrap_or_else(|| InputEvent {
             time: SystemTime::now(),
-            event: Interaction::WindowEvent(WindowEvent::WindowResized),
-        };
-    } else if bytes.len() % 6 == 0 && bytes[..3] == [27, 91, 77] {
-        // mouse
-        return InputEvent {
-            time: SystemTime::now(),
-            event: Interaction::MouseEvent(decode_mi(bytes).remove(0)),
-        };
-    } else if bytes.len() < 9 {
-        // BUG: 'لا' arabic char breaks the decode_ki function since it's 2 unicode chars combined char
-        // i could use decode_ki_kai and take the first char only, but that breaks the combined
-        // char
-        // TODO: implement unicode combined chars support
-        // keyboard
-        return InputEvent {
+            event: Interaction::Unknown(vec![]),
+        });
+    }
+
+    let mut parser = InputParser::new();
+    let mut events = parser.feed(bytes);
+    events.append(&mut parser.finish());
+
+    events
+        .into_iter()
+        .next()
+        .unwrap_or_else(|| InputEvent {
             time: SystemTime::now(),
-            event: Interaction::KbdEvent(decode_ki(bytes).unwrap()),
-            // event: Interaction::KbdEvent(decode_ki_kai(bytes.to_vec()).remove(0).unwrap()),
-        };
+            event: Interaction::Unknown(bytes.to_vec()),
+        })
+}
+
+// the event for a read that returned no bytes
+// None if a signal woke the read up but has nothing to report
+pub(crate) fn woken(ws: &mut winsize) -> Option<InputEvent> {
+    let event = if CLOSED.get() {
+        Interaction::Closed
+    } else if let Some(signal) = REPLAYED.take() {
+        // a replayed suspension doesn't stop the program again, nor does a resize change ws
+        Interaction::WindowEvent(signal.event()?)
     } else {
-        // paste
-        return InputEvent {
-            event: Interaction::PasteEvent(PasteEvent(
-                decode_ki_kai(bytes.to_vec())
-                    .into_iter()
-                    .filter(|r| r.is_ok())
-                    .map(|r| r.unwrap())
-                    .filter(|c| c.is_char())
-                    .map(|c| {
-                        let Char::Char(ch) = c.char else {
-                            unreachable!()
-                        };
-                        ch
-                    })
-                    .collect::<String>(),
-            )),
-            time: SystemTime::now(),
-        };
-    }
+        // a suspension comes first, the window may have been resized while the program was stopped
+        let we = suspend::take()
+            .map(|we| {
+                // the resumed event stands for any resize too, the program redraws everything on it
+                resize::take(ws);
+                *ws = winsize::from_ioctl();
+                we
+            })
+            .or_else(|| resize::take(ws))?;
+
+        Interaction::WindowEvent(we)
+    };
+
+    Some(InputEvent {
+        time: SystemTime::now(),
+        event,
+    })
 }
 
 #[derive(Debug)]
@@ -84,6 +276,50 @@ pub enum Interaction {
     KbdEvent(KbdEvent),
     MouseEvent(MouseEvent),
     PasteEvent(PasteEvent),
+    /// the clipboard contents the terminal sent back, see console::request_clipboard
+    ClipboardEvent(ClipboardEvent),
+    /// user defined event, injected through an EventSender
+    Custom(CustomEvent),
+    /// stdin is closed, e.g., at end of file or after the terminal went away
+    /// no more input will come, every following read returns right away
+    Closed,
+    /// bytes that could not be decoded into any other event
+    /// e.g., a sequence the decoders don't know of, or a terminal reply
+    Unknown(Vec<u8>),
+}
+
+/// errors for the keyboard and mouse input decoders
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeError {
+    /// the bytes stop in the middle of a sequence
+    /// holds the bytes of the unfinished sequence
+    Incomplete(Vec<u8>),
+    /// the bytes are not any known sequence
+    /// holds the offending bytes
+    Unknown(Vec<u8>),
+}
+
+/// a user defined event value
+/// e.g., the result of some network request or file watcher running in another thread
+pub struct CustomEvent(pub Box<dyn Any + Send>);
+
+impl std::fmt::Debug for CustomEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "CustomEvent")
+    }
+}
+
+impl CustomEvent {
+    /// returns a reference to the inner value if it is of type T
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.0.downcast_ref::<T>()
+    }
+
+    /// takes the inner value out if it is of type T
+    /// otherwise gives the event back
+    pub fn downcast<T: Any>(self) -> Result<T, Self> {
+        self.0.downcast::<T>().map(|v| *v).map_err(Self)
+    }
 }
 
 #[derive(Debug)]
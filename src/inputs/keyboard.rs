@@ -1,24 +1,66 @@
+/// grapheme cluster segmentation, keeps composed chars and emoji sequences in one piece
+pub mod grapheme;
+/// kitty keyboard protocol; key release/repeat reporting and unambiguous key codes
+pub mod kitty;
+
+/// vt220, xterm, rxvt and linux console key sequences, and the application keypad mode
+pub mod vt;
+
+pub use kitty::{decode_kitty, disable_kitty_keyboard, enable_kitty_keyboard};
+pub use vt::{decode_vt, disable_keypad_mode, enable_keypad_mode, encode_ki};
+
 use std::collections::VecDeque;
 use std::io::BufRead;
-use std::io::Error;
 use std::io::Read;
 use std::io::StdinLock;
 use std::io::Write;
 
+use super::DecodeError;
+
 /// char enum
 /// could be either a control character (cc) or a normal utf-8 char
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Char {
     /// control character such as \r (carriage return)
     CC(CC),
     /// utf-8 character
     Char(char),
+    /// many utf-8 chars that make up one user perceived character
+    /// e.g., a letter and its combining accent, a zwj emoji sequence or 'لا'
+    /// should be inserted and deleted as one unit by whatever keeps the typed text;
+    /// Text values hold one char per cell and don't keep clusters together, see Text::value
+    Grapheme(String),
 }
 
 /// paste event for when ctrl+v is hit
+/// holds the exact pasted text, newlines and tabs included
+/// requires bracketed paste mode, see console::enable_bracketed_paste
 #[derive(Debug)]
 pub struct PasteEvent(pub String);
 
+/// the sequence a terminal sends before pasted text in bracketed paste mode
+pub const PASTE_START: &[u8] = b"\x1b[200~";
+/// the sequence a terminal sends after pasted text in bracketed paste mode
+pub const PASTE_END: &[u8] = b"\x1b[201~";
+
+/// decodes a bracketed paste; ESC[200~ text ESC[201~
+/// returns None if the bytes do not start with the paste start sequence
+/// a missing end sequence means the paste was cut short, everything after the start is taken
+pub fn decode_paste(bytes: &[u8]) -> Option<PasteEvent> {
+    let text = bytes.strip_prefix(PASTE_START)?;
+    let text = match find(text, PASTE_END) {
+        Some(end) => &text[..end],
+        None => text,
+    };
+
+    Some(PasteEvent(String::from_utf8_lossy(text).into_owned()))
+}
+
+// returns the index of the first occurence of pat in bytes
+pub(crate) fn find(bytes: &[u8], pat: &[u8]) -> Option<usize> {
+    bytes.windows(pat.len()).position(|w| w == pat)
+}
+
 impl Default for Char {
     fn default() -> Self {
         Self::Char(' ')
@@ -51,45 +93,46 @@ impl Char {
         })
     }
 
-    /// can't fail
-    fn from_utf8(bytes: &[u8]) -> Self {
-        Char::Char(std::str::from_utf8(bytes).unwrap().chars().last().unwrap())
+    /// None if the bytes are not valid utf8
+    fn from_utf8(bytes: &[u8]) -> Option<Self> {
+        Some(Char::Char(std::str::from_utf8(bytes).ok()?.chars().last()?))
     }
 
-    fn from_arrow_key(byte: u8) -> Self {
-        Char::CC(match byte {
+    fn from_arrow_key(byte: u8) -> Option<Self> {
+        Some(Char::CC(match byte {
             65 => CC::Up,
             66 => CC::Down,
             67 => CC::Right,
             68 => CC::Left,
-            _ => unreachable!("already asserted this byte to be between 65 and 68"),
-        })
+            _ => return None,
+        }))
     }
 
     // fn keys that generate 3 bytes long input
-    fn from_fn_key3(byte: u8) -> Self {
-        Char::CC(match byte {
+    fn from_fn_key3(byte: u8) -> Option<Self> {
+        Some(Char::CC(match byte {
             80 => CC::F1,
             81 => CC::F2,
             82 => CC::F3,
             83 => CC::F4,
-            _ => unreachable!("already asserted this byte to be between 80 and 83"),
-        })
+            _ => return None,
+        }))
     }
 
-    fn from_cc_extra(byte: u8) -> Self {
-        Char::CC(match byte {
-            51 => CC::Insert,
+    fn from_cc_extra(byte: u8) -> Option<Self> {
+        Some(Char::CC(match byte {
+            50 => CC::Insert,
+            51 => CC::Delete,
             52 => CC::End,
             54 => CC::PageDown,
             53 => CC::PageUp,
             49 => CC::Home,
-            _ => unreachable!("already asserted this byte to be between 80 and 83"),
-        })
+            _ => return None,
+        }))
     }
 
-    fn from_fn_key5(byte2: u8, byte3: u8) -> Self {
-        Char::CC(match byte3 {
+    fn from_fn_key5(byte2: u8, byte3: u8) -> Option<Self> {
+        Some(Char::CC(match byte3 {
             53 if byte2 == 49 => CC::F5,
             55 if byte2 == 49 => CC::F6,
             56 if byte2 == 49 => CC::F7,
@@ -98,41 +141,43 @@ impl Char {
             49 if byte2 == 50 => CC::F10,
             51 if byte2 == 50 => CC::F11,
             52 if byte2 == 50 => CC::F12,
-            _ => unreachable!(
-                "already asserted these bytes, 
-                if you really do reach here, it's because
-                i let some cases get away in my assertions\r\n
-                Please open an issue here \"https://github.com/uraneko/ragout\" 
-                so that i can fix the bug"
-            ),
-        })
+            _ => return None,
+        }))
     }
 
     // fn from_6b_with_mods(bytes: &[u8]) -> Self {}
 
-    fn from_utf81(bytes: &[u8]) -> Self {
-        assert_eq!(bytes.len(), 1);
+    fn from_utf81(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != 1 {
+            return None;
+        }
 
         Self::from_utf8(bytes)
     }
-    fn from_utf82(bytes: &[u8]) -> Self {
-        assert_eq!(bytes.len(), 2);
+    fn from_utf82(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != 2 {
+            return None;
+        }
 
         Self::from_utf8(bytes)
     }
-    fn from_utf83(bytes: &[u8]) -> Self {
-        assert_eq!(bytes.len(), 3);
+    fn from_utf83(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != 3 {
+            return None;
+        }
 
         Self::from_utf8(bytes)
     }
-    fn from_utf84(bytes: &[u8]) -> Self {
-        assert_eq!(bytes.len(), 4);
+    fn from_utf84(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() != 4 {
+            return None;
+        }
 
         Self::from_utf8(bytes)
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 /// control characters that represent well established escape sequence or should not be printed as
 /// normal chars
 pub enum CC {
@@ -155,7 +200,9 @@ pub enum CC {
     /// arrow left key
     Left,
     /// insert key
-    Insert, // Del
+    Insert,
+    /// delete key
+    Delete,
     /// home key
     Home,
     /// end key
@@ -165,10 +212,9 @@ pub enum CC {
     /// page down key
     PageDown,
     // fn keys
-    /// >[!NOTE] usually adding a modifier like ctrl or shift to a Function key results in a number
-    /// > incrementation
-    /// > this module does not handle those incrementations
-    /// > so a shift + F5 that would normally be an F17 is considered a F5 + SHIFT mod here
+    /// >[!NOTE] terminals send shift + F1..F12 as the F13..F24 keys; xterm names them that way
+    /// > so a shift + F5 is decoded as an F17, not as an F5 + SHIFT mod
+    /// > F16 and the menu key share the same sequence in some terminals, it is decoded as the menu key
     /// f1 key
     F1,
     /// f2 key
@@ -193,6 +239,76 @@ pub enum CC {
     F11,
     /// f12 key
     F12,
+    /// f13 key
+    F13,
+    /// f14 key
+    F14,
+    /// f15 key
+    F15,
+    /// f16 key
+    F16,
+    /// f17 key
+    F17,
+    /// f18 key
+    F18,
+    /// f19 key
+    F19,
+    /// f20 key
+    F20,
+    /// f21 key
+    F21,
+    /// f22 key
+    F22,
+    /// f23 key
+    F23,
+    /// f24 key
+    F24,
+    /// menu key, the one next to the right ctrl
+    Menu,
+    /// print screen key
+    /// only reported by the kitty keyboard protocol
+    PrintScreen,
+    // keypad keys
+    /// >[!NOTE] the keypad only sends these in application keypad mode, see enable_keypad_mode
+    /// > otherwise it sends the same bytes as the main keyboard keys
+    /// keypad 0 key
+    Keypad0,
+    /// keypad 1 key
+    Keypad1,
+    /// keypad 2 key
+    Keypad2,
+    /// keypad 3 key
+    Keypad3,
+    /// keypad 4 key
+    Keypad4,
+    /// keypad 5 key
+    Keypad5,
+    /// keypad 6 key
+    Keypad6,
+    /// keypad 7 key
+    Keypad7,
+    /// keypad 8 key
+    Keypad8,
+    /// keypad 9 key
+    Keypad9,
+    /// keypad . key
+    KeypadDecimal,
+    /// keypad / key
+    KeypadDivide,
+    /// keypad * key
+    KeypadMultiply,
+    /// keypad - key
+    KeypadSubtract,
+    /// keypad + key
+    KeypadAdd,
+    /// keypad enter key
+    KeypadEnter,
+    /// keypad = key
+    KeypadEqual,
+    /// keypad , key
+    KeypadSeparator,
+    /// keypad 5 key with num lock off
+    KeypadBegin,
 }
 
 /// the keyboard event modifiers struct
@@ -202,31 +318,34 @@ pub struct Modifiers(pub u8);
 
 impl std::fmt::Display for Modifiers {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.as_text(),)
+        match self.as_text() {
+            Some(text) => write!(f, "{}", text),
+            // only reachable by building Modifiers by hand
+            None => write!(f, "Modifiers({})", self.0),
+        }
     }
 }
 
 impl std::fmt::Debug for Modifiers {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.as_text())
+        std::fmt::Display::fmt(self, f)
     }
 }
 
-const SUPER: u8 = 0x01;
-const CONTROL: u8 = 0x02;
-const ALT: u8 = 0x04;
-const SHIFT: u8 = 0x08;
-const NONE: u8 = 0x0;
+pub(crate) const SUPER: u8 = 0x01;
+pub(crate) const CONTROL: u8 = 0x02;
+pub(crate) const ALT: u8 = 0x04;
+pub(crate) const SHIFT: u8 = 0x08;
+pub(crate) const NONE: u8 = 0x0;
 
 impl Modifiers {
-    fn from_byte(byte: u8) -> Self {
-        assert!(byte < 16);
-
-        Self(byte)
+    fn from_byte(byte: u8) -> Option<Self> {
+        (byte < 16).then_some(Self(byte))
     }
 
-    fn as_text(&self) -> &str {
-        match self.0 {
+    // None if the value is not a combination of control(2), shift(8), alt(4) and super(1)
+    fn as_text(&self) -> Option<&'static str> {
+        Some(match self.0 {
             0 => "NONE",
             1 => "SUPER",
             2 => "CONTROL",
@@ -243,32 +362,25 @@ impl Modifiers {
             13 => "SUPER_SHIFT_ALT",
             14 => "CONTROL_SHIFT_ALT",
             15 => "CONTROL_SHIFT_ALT_SUPER",
-
-            _ => unreachable!(
-                "basic values are only control(2), shift(8), alt(4) and super(1)
-                    \r\n you did not present a valid combination of those values"
-            ),
-        }
+            _ => return None,
+        })
     }
 
-    fn from_or(bytes: &[u8]) -> Self {
-        let modifier = Self(bytes.into_iter().fold(0, |acc, b| acc | b));
-        assert!(modifier.0 < 16);
-
-        modifier
+    // None if the bytes are not modifier bits
+    fn from_or(bytes: &[u8]) -> Option<Self> {
+        Self::from_byte(bytes.iter().fold(0, |acc, b| acc | b))
     }
 
+    // bits outside the 4 modifiers are left out
     fn or(&mut self, byte: u8) {
-        assert!(self.0 | byte < 16);
-
-        self.0 |= byte;
+        self.0 = (self.0 | byte) & (SUPER | CONTROL | ALT | SHIFT);
     }
 
     // 6 bytes escape sequence modifiers identification
     // the modifier byte is bytes[4] (the 5th byte)
     // in the inputted escape sequence
-    fn from_raw67(byte: u8) -> Self {
-        Self(match byte {
+    fn from_raw67(byte: u8) -> Option<Self> {
+        Some(Self(match byte {
             50 => SHIFT,
             51 => ALT,
             52 => SHIFT | ALT,
@@ -277,16 +389,13 @@ impl Modifiers {
             55 => CONTROL | ALT,
             56 => CONTROL | SHIFT | ALT,
             57 => SUPER,
-            _ => unreachable!(
-                "What manner of key combinations did 
-                you input to get this byte, sir?",
-            ),
-        })
+            _ => return None,
+        }))
     }
 
     // 7 bytes escape sequence modifiers identification
-    fn from_super7(byte: u8) -> Self {
-        Self(match byte {
+    fn from_super7(byte: u8) -> Option<Self> {
+        Some(Self(match byte {
             48 => 9,
             49 => 5,
             50 => 13,
@@ -294,18 +403,31 @@ impl Modifiers {
             52 => 11,
             53 => 7,
             54 => 15,
-            val => unreachable!("all mods handled, who are you? {}", val),
-        })
+            _ => return None,
+        }))
     }
 }
 
 /// the keyboard input event struct
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct KbdEvent {
     /// char can be a control char or a normal utf-8 char
     pub char: Char,
     /// keyboard input event modifiers
     pub modifiers: Modifiers,
+    /// whether the key got pressed, repeated or released
+    /// only the kitty keyboard protocol reports repeats and releases
+    pub kind: KeyKind,
+}
+
+/// the kind of a keyboard input event
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum KeyKind {
+    #[default]
+    Press,
+    /// the key is being held down
+    Repeat,
+    Release,
 }
 
 impl Default for KbdEvent {
@@ -313,17 +435,14 @@ impl Default for KbdEvent {
         Self {
             modifiers: Modifiers(0x0),
             char: Char::CC(CC::ESC),
+            kind: KeyKind::Press,
         }
     }
 }
 
 impl KbdEvent {
     pub fn is_char(&self) -> bool {
-        if let Char::Char(_) = self.char {
-            true
-        } else {
-            false
-        }
+        matches!(self.char, Char::Char(_) | Char::Grapheme(_))
     }
 
     pub fn is_cc(&self) -> bool {
@@ -360,7 +479,7 @@ mod utf8_decoder {
     /// - ascii char
     /// - ascii control char (cr, bs, esc or tab)
     /// - ascii char with ctrl modifier
-    fn decode_1_byte(byte: u8, ke: &mut KbdEvent) {
+    fn decode_1_byte(byte: u8, ke: &mut KbdEvent) -> Result<(), DecodeError> {
         match byte {
             0..=31 | 127 => match byte {
                 9 | 13 | 27 | 127 => ke.char = Char::from_cc(byte),
@@ -368,12 +487,16 @@ mod utf8_decoder {
                     *ke = KbdEvent {
                         char: Char::from_ctrl_ascii(byte),
                         modifiers: Modifiers(CONTROL),
+                        kind: KeyKind::Press,
                     }
                 }
             },
             32..=126 => ke.char = Char::from_ascii(byte),
-            _ => unreachable!("ascii are limited by 7 bits a byte, so from 0 to 127"),
+            // ascii are limited by 7 bits a byte, so from 0 to 127
+            _ => return Err(DecodeError::Unknown(vec![byte])),
         }
+
+        Ok(())
     }
 
     /// three cases
@@ -382,154 +505,135 @@ mod utf8_decoder {
     /// - modifier byte (alt) followed by modifier (ctrl) altered ascii byte/value
     /// easy problem, since, in utf8 rules, a 2 bytes utf8 value MUST have a first byte that has
     /// 110 as its highest 3 bits and a second byte that has 10 as its highest 2 bits
-    fn decode_2_bytes(bytes: &[u8], ke: &mut KbdEvent) {
-        assert_eq!(bytes.len(), 2);
+    fn decode_2_bytes(bytes: &[u8], ke: &mut KbdEvent) -> Result<(), DecodeError> {
         // a 2 bytes utf8 value or an ascii + alt
         if is_utf82(bytes[0], bytes[1]) {
-            ke.char = Char::from_utf8(bytes);
-        } else {
-            assert_eq!(27, bytes[0]);
-            decode_1_byte(bytes[1], ke);
+            ke.char = Char::from_utf8(bytes).ok_or_else(|| unknown(bytes))?;
+        } else if bytes[0] == 27 {
+            decode_1_byte(bytes[1], ke).map_err(|_| unknown(bytes))?;
             ke.modifiers.or(ALT);
+        } else {
+            return Err(unknown(bytes));
         }
+
+        Ok(())
     }
 
     // cases
     // - 3 bytes utf8
     // - arrow key
     // - fn keys
-    fn decode_3_bytes(bytes: &[u8], ke: &mut KbdEvent) {
-        assert_eq!(bytes.len(), 3);
+    fn decode_3_bytes(bytes: &[u8], ke: &mut KbdEvent) -> Result<(), DecodeError> {
         // valid '3 bytes' utf8 first byte
         if is_utf83(bytes[0], bytes[1], bytes[2]) {
-            ke.char = Char::from_utf8(bytes);
+            ke.char = Char::from_utf8(bytes).ok_or_else(|| unknown(bytes))?;
+        } else if bytes[0] == 27 && bytes[1] == 91 && bytes[2] == 90 {
+            ke.modifiers.or(SHIFT);
+            ke.char = Char::CC(CC::TAB);
+        } else if bytes[0] == 27 && bytes[1] == 91 {
+            ke.char = Char::from_arrow_key(bytes[2]).ok_or_else(|| unknown(bytes))?;
+        } else if bytes[0] == 27 && bytes[1] == 79 {
+            ke.char = Char::from_fn_key3(bytes[2]).ok_or_else(|| unknown(bytes))?;
         } else {
-            assert_eq!(bytes[0], 27);
-            match bytes[1] {
-                91 => {
-                    if bytes[2] == 90 {
-                        ke.modifiers.or(SHIFT);
-                        ke.char = Char::CC(CC::TAB);
-                    } else {
-                        assert!(bytes[2] >= 65 && bytes[2] <= 68);
-                        ke.char = Char::from_arrow_key(bytes[2]);
-                    }
-                }
-                79 => {
-                    assert!(bytes[2] >= 80 && bytes[2] <= 83);
-                    ke.char = Char::from_fn_key3(bytes[2]);
-                }
-                _val => unreachable!(
-                    "do not know of this byte combination: {:?}, 
-                what manner of key did you press, sir?",
-                    bytes
-                ),
-            }
+            return Err(unknown(bytes));
         }
+
+        Ok(())
     }
 
-    fn decode_4_bytes(bytes: &[u8], ke: &mut KbdEvent) {
-        assert_eq!(bytes.len(), 4);
+    fn decode_4_bytes(bytes: &[u8], ke: &mut KbdEvent) -> Result<(), DecodeError> {
         if is_utf84(bytes[0], bytes[1], bytes[2], bytes[3]) {
-            ke.char = Char::from_utf8(bytes);
+            ke.char = Char::from_utf8(bytes).ok_or_else(|| unknown(bytes))?;
+        } else if bytes[0] == 27 && bytes[1] == 91 && bytes[3] == 126 {
+            ke.char = Char::from_cc_extra(bytes[2]).ok_or_else(|| unknown(bytes))?;
         } else {
-            assert!(bytes[0] == 27 && bytes[1] == 91 && bytes[3] == 126);
-            ke.char = Char::from_cc_extra(bytes[2]);
+            return Err(unknown(bytes));
         }
+
+        Ok(())
     }
 
     // not utf8 anymore
-    fn decode_5_bytes(bytes: &[u8], ke: &mut KbdEvent) {
-        assert_eq!(bytes.len(), 5);
-        assert!(
-            bytes[0] == 27 && bytes[1] == 91 && bytes[4] == 126,
-            "{bytes:?}"
-        );
-        assert!(bytes[2] == 50 || bytes[2] == 49);
-        ke.char = Char::from_fn_key5(bytes[2], bytes[3]);
+    fn decode_5_bytes(bytes: &[u8], ke: &mut KbdEvent) -> Result<(), DecodeError> {
+        if !(bytes[0] == 27 && bytes[1] == 91 && bytes[4] == 126) {
+            return Err(unknown(bytes));
+        }
+        ke.char = Char::from_fn_key5(bytes[2], bytes[3]).ok_or_else(|| unknown(bytes))?;
+
+        Ok(())
     }
 
     // not utf8
     // 6 bytes < 7 means that there is not a modifiers combination of SUPER + mod(s)
-    fn decode_6_bytes(bytes: &[u8], ke: &mut KbdEvent) {
+    fn decode_6_bytes(bytes: &[u8], ke: &mut KbdEvent) -> Result<(), DecodeError> {
         // escape sequence
-        assert_eq!(bytes.len(), 6);
-        assert!(bytes[0] == 27 && bytes[1] == 91);
-        assert!(bytes[3] == 59);
-        assert!([49, 51, 53, 54].contains(&bytes[2]));
-        if bytes[2] == 49 {
-            ke.modifiers = Modifiers::from_raw67(bytes[4]);
-            ke.char = if [65, 66, 67, 68].contains(&bytes[5]) {
-                Char::from_arrow_key(bytes[5])
-            } else if bytes[5] != 70 && bytes[5] != 72 {
-                assert!([80, 81, 82, 83].contains(&bytes[5]), "{bytes:?}");
-                Char::from_fn_key3(bytes[5])
-            } else {
-                assert!(bytes[5] == 70 || bytes[5] == 72);
-                if bytes[5] == 70 {
-                    Char::CC(CC::End)
-                } else {
-                    Char::CC(CC::Home)
-                }
-            }
-        } else {
-            assert!([51, 53, 54, 49].contains(&bytes[2]));
-            ke.modifiers = Modifiers::from_raw67(bytes[4]);
-            ke.char = if bytes[2] == 49 {
-                match bytes[5] {
-                    70 => Char::CC(CC::End),
-                    72 => Char::CC(CC::Home),
-                    _ => unreachable!("unhandled escape sequence; {:?}", bytes),
-                }
-            } else {
-                assert_eq!(bytes[5], 126);
-                Char::from_cc_extra(bytes[2])
-            }
+        if !(bytes[0] == 27 && bytes[1] == 91 && bytes[3] == 59) {
+            return Err(unknown(bytes));
         }
+        ke.modifiers = Modifiers::from_raw67(bytes[4]).ok_or_else(|| unknown(bytes))?;
+        ke.char = match (bytes[2], bytes[5]) {
+            (49, 65..=68) => Char::from_arrow_key(bytes[5]),
+            (49, 80..=83) => Char::from_fn_key3(bytes[5]),
+            (49, 70) => Some(Char::CC(CC::End)),
+            (49, 72) => Some(Char::CC(CC::Home)),
+            (51 | 53 | 54, 126) => Char::from_cc_extra(bytes[2]),
+            _ => None,
+        }
+        .ok_or_else(|| unknown(bytes))?;
+
+        Ok(())
     }
 
-    fn decode_7_bytes(bytes: &[u8], ke: &mut KbdEvent) {
-        assert_eq!(bytes.len(), 7);
+    fn decode_7_bytes(bytes: &[u8], ke: &mut KbdEvent) -> Result<(), DecodeError> {
         // escape sequence
-        assert!(bytes[0] == 27 && bytes[1] == 91);
+        if !(bytes[0] == 27 && bytes[1] == 91) {
+            return Err(unknown(bytes));
+        }
         match bytes[6] == 126 && bytes[4] == 59 {
             // fn key + some ctrl, shift, alt mods combination
             true => {
-                ke.modifiers = Modifiers::from_raw67(bytes[5]);
-                ke.char = Char::from_fn_key5(bytes[2], bytes[3]);
+                ke.modifiers = Modifiers::from_raw67(bytes[5]).ok_or_else(|| unknown(bytes))?;
+                ke.char = Char::from_fn_key5(bytes[2], bytes[3]).ok_or_else(|| unknown(bytes))?;
             }
             // arrow keys/extra cc + some super included mods combination
             false => {
-                assert_eq!(bytes[4], 49);
-                ke.modifiers = Modifiers::from_super7(bytes[5]);
-                match bytes[2] {
-                    49 if [65, 66, 67, 68].contains(&bytes[6]) => {
-                        ke.char = Char::from_arrow_key(bytes[6])
-                    }
-                    49 if bytes[6] == 70 => ke.char = Char::CC(CC::End),
-                    49 if bytes[6] == 72 => ke.char = Char::CC(CC::Home),
-                    51 | 53 | 54 if bytes[6] == 126 => ke.char = Char::from_cc_extra(bytes[2]),
-                    49 if [80, 81, 82, 83].contains(&bytes[6]) => {
-                        ke.char = Char::from_fn_key3(bytes[6])
-                    }
-                    _ => unreachable!("key combination: {:?} is unhandled", bytes),
+                if bytes[4] != 49 {
+                    return Err(unknown(bytes));
+                }
+                ke.modifiers = Modifiers::from_super7(bytes[5]).ok_or_else(|| unknown(bytes))?;
+                ke.char = match bytes[2] {
+                    49 if [65, 66, 67, 68].contains(&bytes[6]) => Char::from_arrow_key(bytes[6]),
+                    49 if bytes[6] == 70 => Some(Char::CC(CC::End)),
+                    49 if bytes[6] == 72 => Some(Char::CC(CC::Home)),
+                    51 | 53 | 54 if bytes[6] == 126 => Char::from_cc_extra(bytes[2]),
+                    49 if [80, 81, 82, 83].contains(&bytes[6]) => Char::from_fn_key3(bytes[6]),
+                    _ => None,
                 }
+                .ok_or_else(|| unknown(bytes))?;
             }
         }
+
+        Ok(())
     }
 
-    fn decode_8_bytes(bytes: &[u8], ke: &mut KbdEvent) {
-        assert_eq!(bytes.len(), 8);
-        assert!({
-            bytes[0] == 27
-                && bytes[1] == 91
-                && [49, 50].contains(&bytes[2])
-                && bytes[4] == 59
-                && bytes[5] == 49
-                && bytes[7] == 126
-        });
-        ke.modifiers = Modifiers::from_super7(bytes[6]);
-        ke.char = Char::from_fn_key5(bytes[2], bytes[3]);
+    fn decode_8_bytes(bytes: &[u8], ke: &mut KbdEvent) -> Result<(), DecodeError> {
+        if !(bytes[0] == 27
+            && bytes[1] == 91
+            && [49, 50].contains(&bytes[2])
+            && bytes[4] == 59
+            && bytes[5] == 49
+            && bytes[7] == 126)
+        {
+            return Err(unknown(bytes));
+        }
+        ke.modifiers = Modifiers::from_super7(bytes[6]).ok_or_else(|| unknown(bytes))?;
+        ke.char = Char::from_fn_key5(bytes[2], bytes[3]).ok_or_else(|| unknown(bytes))?;
+
+        Ok(())
+    }
+
+    fn unknown(bytes: &[u8]) -> DecodeError {
+        DecodeError::Unknown(bytes.to_vec())
     }
 
     // WARN: design flow
@@ -542,21 +646,43 @@ mod utf8_decoder {
     // INFO: this only works under the premise of receiving 1 input event at once
     // basically no ctrl-v or any way to dump multiple chars of input at once
     /// decodes the bytes from 1 keyboard input event into a KbdEvent instance
-    pub fn decode_ki(bytes: &[u8]) -> Option<KbdEvent> {
+    ///
+    /// # Errors
+    ///
+    /// returns DecodeError::Unknown if the bytes are not a known key sequence
+    /// and DecodeError::Incomplete if there are no bytes at all
+    pub fn decode_ki(bytes: &[u8]) -> Result<KbdEvent, DecodeError> {
+        // the sequences of other terminals and the kitty protocol don't fit the byte count rules
+        decode_legacy(bytes)
+            .or_else(|e| decode_kitty(bytes).or_else(|| decode_vt(bytes)).ok_or(e))
+            .map(vt::fold_shift)
+    }
+
+    fn decode_legacy(bytes: &[u8]) -> Result<KbdEvent, DecodeError> {
         let mut ke: KbdEvent = Default::default();
+
+        // many chars typed with one key press or committed by an input method
+        if let Ok(s) = std::str::from_utf8(bytes) {
+            if s.chars().nth(1).is_some() && grapheme::cluster_len(s) == s.len() {
+                ke.char = Char::Grapheme(s.to_string());
+                return Ok(ke);
+            }
+        }
+
         match bytes.len() {
-            1 => decode_1_byte(bytes[0], &mut ke),
-            2 => decode_2_bytes(bytes, &mut ke),
-            3 => decode_3_bytes(bytes, &mut ke),
-            4 => decode_4_bytes(bytes, &mut ke),
-            5 => decode_5_bytes(bytes, &mut ke),
-            6 => decode_6_bytes(bytes, &mut ke),
-            7 => decode_7_bytes(bytes, &mut ke),
-            8 => decode_8_bytes(bytes, &mut ke),
-            _ => return None,
+            0 => return Err(DecodeError::Incomplete(vec![])),
+            1 => decode_1_byte(bytes[0], &mut ke)?,
+            2 => decode_2_bytes(bytes, &mut ke)?,
+            3 => decode_3_bytes(bytes, &mut ke)?,
+            4 => decode_4_bytes(bytes, &mut ke)?,
+            5 => decode_5_bytes(bytes, &mut ke)?,
+            6 => decode_6_bytes(bytes, &mut ke)?,
+            7 => decode_7_bytes(bytes, &mut ke)?,
+            8 => decode_8_bytes(bytes, &mut ke)?,
+            _ => return Err(unknown(bytes)),
         }
 
-        Some(ke)
+        Ok(ke)
     }
 
     // utf8 2 - 3 - 4 bytes values are easy to tell
@@ -582,8 +708,8 @@ mod utf8_decoder {
     // as this fn can handle a ctrl-v of input
     /// decodes a vector of some number of keyboard input events bytes
     /// returns a vector of KbdEvents wrapped in Results
-    pub fn decode_ki_kai(bytes: Vec<u8>) -> Vec<Result<KbdEvent, Error>> {
-        let mut v: Vec<Result<KbdEvent, Error>> = vec![];
+    pub fn decode_ki_kai(bytes: Vec<u8>) -> Vec<Result<KbdEvent, DecodeError>> {
+        let mut v: Vec<Result<KbdEvent, DecodeError>> = vec![];
 
         let mut bytes = bytes.into_iter();
         while let Some(b0) = bytes.next() {
@@ -594,7 +720,8 @@ mod utf8_decoder {
                     if b1.is_none() {
                         v.push(Ok(KbdEvent {
                             char: Char::CC(CC::ESC),
-                            modifiers: Modifiers::from_byte(0),
+                            modifiers: Modifiers(NONE),
+                            kind: KeyKind::Press,
                         }));
 
                         return v;
@@ -606,30 +733,25 @@ mod utf8_decoder {
 
                     if b2.is_none() {
                         let mut ke = Default::default();
-                        decode_2_bytes(&[b0, b1], &mut ke);
-                        v.push(Ok(ke));
+                        v.push(decode_2_bytes(&[b0, b1], &mut ke).map(|_| ke));
 
                         return v;
                     }
 
                     let b2 = b2.unwrap();
 
-                    match b1 == 91 || b1 == 79 {
+                    match b1 {
                         // surely 3 bytes
-                        true if b1 == 79 => {
-                            assert!((80..=83).contains(&b2));
+                        79 => {
                             let mut ke = Default::default();
-                            decode_3_bytes(&[b0, b1, b2], &mut ke);
-
-                            v.push(Ok(ke));
+                            v.push(decode_3_bytes(&[b0, b1, b2], &mut ke).map(|_| ke));
                         }
                         // 3 to 8 bytes esc seq
-                        true if b1 == 91 => {
+                        91 => {
                             let mut ke = Default::default();
                             let b3 = bytes.next();
                             if b3.is_none() {
-                                decode_3_bytes(&[b0, b1, b2], &mut ke);
-                                v.push(Ok(ke));
+                                v.push(decode_3_bytes(&[b0, b1, b2], &mut ke).map(|_| ke));
 
                                 return v;
                             }
@@ -637,17 +759,16 @@ mod utf8_decoder {
                             let b3 = b3.unwrap();
                             match b3 {
                                 126 => {
-                                    decode_4_bytes(&[b0, b1, b2, b3], &mut ke);
-                                    v.push(Ok(ke));
+                                    v.push(decode_4_bytes(&[b0, b1, b2, b3], &mut ke).map(|_| ke));
                                 }
 
                                 59 => {
                                     let b4 = bytes.next();
                                     let b5 = bytes.next();
                                     if b4.is_none() || b5.is_none() {
-                                        v.push(Err(Error::other(
-                                            "there should have been a 5th and 6th bytes here",
-                                        )));
+                                        let mut rest = vec![b0, b1, b2, b3];
+                                        rest.extend(b4);
+                                        v.push(Err(DecodeError::Incomplete(rest)));
                                         return v;
                                     }
 
@@ -656,8 +777,10 @@ mod utf8_decoder {
                                     let b6 = bytes.next();
 
                                     if b6.is_none() {
-                                        decode_6_bytes(&[b0, b1, b2, b3, b4, b5], &mut ke);
-                                        v.push(Ok(ke));
+                                        v.push(
+                                            decode_6_bytes(&[b0, b1, b2, b3, b4, b5], &mut ke)
+                                                .map(|_| ke),
+                                        );
 
                                         return v;
                                     }
@@ -666,81 +789,92 @@ mod utf8_decoder {
 
                                     match b6 {
                                         126 | 80..=83 | 70 | 72 | 65..=68 => {
-                                            decode_7_bytes(&[b0, b1, b2, b3, b4, b5, b6], &mut ke);
-                                            v.push(Ok(ke));
+                                            v.push(
+                                                decode_7_bytes(
+                                                    &[b0, b1, b2, b3, b4, b5, b6],
+                                                    &mut ke,
+                                                )
+                                                .map(|_| ke),
+                                            );
                                         }
                                         _ => {
-                                            decode_6_bytes(&[b0, b1, b2, b3, b4, b5], &mut ke);
+                                            let res =
+                                                decode_6_bytes(&[b0, b1, b2, b3, b4, b5], &mut ke);
                                             bytes = [b6]
                                                 .into_iter()
                                                 .chain(bytes)
                                                 .collect::<Vec<u8>>()
                                                 .into_iter();
-                                            v.push(Ok(ke));
+                                            v.push(res.map(|_| ke));
                                         }
                                     }
                                 }
                                 _ => {
                                     let b4 = bytes.next();
                                     if b4.is_none() {
-                                        v.push(Err(Error::other(
-                                            "there should have been a 5th and 6th bytes here",
-                                        )));
+                                        v.push(Err(DecodeError::Incomplete(vec![b0, b1, b2, b3])));
                                         return v;
                                     }
                                     let b4 = b4.unwrap();
 
                                     match b4 == 126 {
                                         true => {
-                                            decode_5_bytes(&[b0, b1, b2, b3, b4], &mut ke);
-                                            v.push(Ok(ke));
+                                            v.push(
+                                                decode_5_bytes(&[b0, b1, b2, b3, b4], &mut ke)
+                                                    .map(|_| ke),
+                                            );
                                         }
                                         false => {
                                             let (b5, b6) = (bytes.next(), bytes.next());
                                             if b5.is_none() || b6.is_none() {
-                                                v.push(Err(Error::other(
-                                            "there should have been a 5th and 6th bytes here",
-                                        )));
+                                                let mut rest = vec![b0, b1, b2, b3, b4];
+                                                rest.extend(b5);
+                                                v.push(Err(DecodeError::Incomplete(rest)));
                                                 return v;
                                             }
 
                                             let (b5, b6) = (b5.unwrap(), b6.unwrap());
                                             match b6 {
                                                 126 => {
-                                                    decode_7_bytes(
-                                                        &[b0, b1, b2, b3, b4, b5, b6],
-                                                        &mut ke,
+                                                    v.push(
+                                                        decode_7_bytes(
+                                                            &[b0, b1, b2, b3, b4, b5, b6],
+                                                            &mut ke,
+                                                        )
+                                                        .map(|_| ke),
                                                     );
-                                                    v.push(Ok(ke));
                                                 }
                                                 48..=54 => {
                                                     let b7 = bytes.next();
                                                     if b7.is_none() {
-                                                        v.push(Err(Error::other(
-                                            "there should have been a 5th and 6th bytes here",
-                                        )));
+                                                        v.push(Err(DecodeError::Incomplete(vec![
+                                                            b0, b1, b2, b3, b4, b5, b6,
+                                                        ])));
                                                         return v;
                                                     }
                                                     let b7 = b7.unwrap();
-                                                    decode_8_bytes(
-                                                        &[b0, b1, b2, b3, b4, b5, b6, b7],
-                                                        &mut ke,
+                                                    v.push(
+                                                        decode_8_bytes(
+                                                            &[b0, b1, b2, b3, b4, b5, b6, b7],
+                                                            &mut ke,
+                                                        )
+                                                        .map(|_| ke),
                                                     );
-                                                    v.push(Ok(ke));
-                                                }
-                                                _ => {
-                                                    unreachable!("you cant get here: bytes7 = {b6}")
                                                 }
+                                                _ => v.push(Err(DecodeError::Unknown(vec![
+                                                    b0, b1, b2, b3, b4, b5, b6,
+                                                ]))),
                                             }
                                         }
                                     }
                                 }
                             }
                         }
-                        false => {
+                        _ => {
                             v.push(Ok(KbdEvent {
                                 char: Char::CC(CC::ESC),
-                                modifiers: Modifiers::from_byte(0),
+                                modifiers: Modifiers(NONE),
+                                kind: KeyKind::Press,
                             }));
                             bytes = [b1, b2]
                                 .into_iter()
@@ -748,21 +882,18 @@ mod utf8_decoder {
                                 .collect::<Vec<u8>>()
                                 .into_iter();
                         }
-                        _ => unreachable!("im impossible to reach"),
                     }
                 }
                 // this arm covers all utf8 possibilities
                 false => match is_utf81(b0) {
-                    true => v.push(Ok({
+                    true => v.push({
                         let mut ke = Default::default();
-                        decode_1_byte(b0, &mut ke);
-
-                        ke
-                    })),
+                        decode_1_byte(b0, &mut ke).map(|_| ke)
+                    }),
                     false => {
                         let b1 = bytes.next();
                         if b1.is_none() {
-                            v.push(Err(Error::other("not a valid utf8 nor an esc seq")));
+                            v.push(Err(DecodeError::Incomplete(vec![b0])));
 
                             return v;
                         }
@@ -772,15 +903,13 @@ mod utf8_decoder {
                         match is_utf82(b0, b1) {
                             true => {
                                 let mut ke = Default::default();
-                                decode_2_bytes(&[b0, b1], &mut ke);
-
-                                v.push(Ok(ke));
+                                v.push(decode_2_bytes(&[b0, b1], &mut ke).map(|_| ke));
                             }
                             false => {
                                 let b2 = bytes.next();
 
                                 if b2.is_none() {
-                                    v.push(Err(Error::other("not a valid utf8 nor an esc seq")));
+                                    v.push(Err(DecodeError::Incomplete(vec![b0, b1])));
 
                                     return v;
                                 }
@@ -790,17 +919,13 @@ mod utf8_decoder {
                                 match is_utf83(b0, b1, b2) {
                                     true => {
                                         let mut ke = Default::default();
-                                        decode_3_bytes(&[b0, b1, b2], &mut ke);
-
-                                        v.push(Ok(ke));
+                                        v.push(decode_3_bytes(&[b0, b1, b2], &mut ke).map(|_| ke));
                                     }
                                     false => {
                                         let b3 = bytes.next();
 
                                         if b3.is_none() {
-                                            v.push(Err(Error::other(
-                                                "not a valid utf8 nor an esc seq",
-                                            )));
+                                            v.push(Err(DecodeError::Incomplete(vec![b0, b1, b2])));
 
                                             return v;
                                         }
@@ -810,13 +935,15 @@ mod utf8_decoder {
                                         match is_utf84(b0, b1, b2, b3) {
                                             true => {
                                                 let mut ke = Default::default();
-                                                decode_4_bytes(&[b0, b1, b2, b3], &mut ke);
-
-                                                v.push(Ok(ke));
+                                                v.push(
+                                                    decode_4_bytes(&[b0, b1, b2, b3], &mut ke)
+                                                        .map(|_| ke),
+                                                );
                                             }
-                                            false => v.push(Err(Error::other(
-                                                "not a valid utf8 nor an esc seq",
-                                            ))),
+                                            // not a valid utf8 nor an esc seq
+                                            false => v.push(Err(DecodeError::Unknown(vec![
+                                                b0, b1, b2, b3,
+                                            ]))),
                                         }
                                     }
                                 }
@@ -851,65 +978,16 @@ mod utf8_decoder {
 
     // utf8 string decoder
     // since the module is called utf8_decoder
-    // returns error if it doesnt find the expected utf8 bytes
-    // crashes if decoding bytes into utf8 str with std::str::from_utf8 returns an error
-    pub fn decode_utf8_string(bytes: &[u8]) -> Result<String, std::io::Error> {
-        let mut s = String::new();
-        let mut bytes = bytes.into_iter();
-
-        while let Some(byte) = bytes.next() {
-            // check how many bytes the next utf8 char carries
-            match is_utf81(*byte) {
-                true => s.push(*byte as char),
-                false => {
-                    let b2 = bytes.next();
-                    let b2 = if b2.is_none() {
-                        return Err(std::io::Error::other("not a valid utf8 string"));
-                    } else {
-                        b2.unwrap()
-                    };
-
-                    match is_utf82(*byte, *b2) {
-                        true => {
-                            s.push_str(std::str::from_utf8(&[*byte, *b2]).expect("not valid utf8"))
-                        }
-                        false => {
-                            let b3 = bytes.next();
-                            let b3 = if b3.is_none() {
-                                return Err(std::io::Error::other("not a valid utf8 string"));
-                            } else {
-                                b3.unwrap()
-                            };
-
-                            match is_utf83(*byte, *b2, *b3) {
-                                true => s.push_str(
-                                    std::str::from_utf8(&[*byte, *b2, *b3])
-                                        .expect("not valid utf8"),
-                                ),
-                                false => {
-                                    let b4 = bytes.next();
-                                    let b4 = if b4.is_none() {
-                                        return Err(std::io::Error::other(
-                                            "not a valid utf8 string",
-                                        ));
-                                    } else {
-                                        b4.unwrap()
-                                    };
-
-                                    assert!(is_utf84(*byte, *b2, *b3, *b4));
-                                    s.push_str(
-                                        std::str::from_utf8(&[*byte, *b2, *b3, *b4])
-                                            .expect("not valid utf8"),
-                                    )
-                                }
-                            }
-                        }
-                    }
-                }
+    // returns an error if the bytes are not valid utf8
+    // the error holds the trailing bytes of a cut char if the bytes stop in the middle of one
+    pub fn decode_utf8_string(bytes: &[u8]) -> Result<String, DecodeError> {
+        match std::str::from_utf8(bytes) {
+            Ok(s) => Ok(s.to_string()),
+            Err(e) if e.error_len().is_none() => {
+                Err(DecodeError::Incomplete(bytes[e.valid_up_to()..].to_vec()))
             }
+            Err(_) => Err(DecodeError::Unknown(bytes.to_vec())),
         }
-
-        Ok(s)
     }
 }
 
@@ -946,7 +1024,6 @@ pub fn kbd_read() {
         std::thread::sleep(std::time::Duration::from_millis(counter));
 
         if n > 0 {
-            print!("stdout len: {}", n);
             let mut filtered = filter_nil(&buf);
             // input_queue.append(&mut filtered);
             print!(
@@ -962,3 +1039,121 @@ pub fn kbd_read() {
         }
     }
 }
+
+#[cfg(test)]
+mod test_paste {
+    use super::decode_paste;
+
+    #[test]
+    fn bracketed() {
+        let paste = decode_paste(b"\x1b[200~line 1\n\tline 2\r\n\x1b[201~").unwrap();
+        assert_eq!(paste.0, "line 1\n\tline 2\r\n");
+
+        let paste = decode_paste("\x1b[200~لا ✓\x1b[201~".as_bytes()).unwrap();
+        assert_eq!(paste.0, "لا ✓");
+    }
+
+    #[test]
+    fn not_a_paste() {
+        assert!(decode_paste(b"hello world, fast typing").is_none());
+        assert!(decode_paste(b"\x1b[1;5A").is_none());
+    }
+}
+
+#[cfg(test)]
+mod test_decode {
+    use super::{decode_ki, decode_ki_kai, decode_kitty, decode_utf8_string, decode_vt, Char, CC};
+    use super::{Modifiers, ALT, CONTROL, SHIFT};
+    use crate::inputs::DecodeError;
+
+    // bytes that show up in input sequences, plus some random ones
+    const ALPHABET: &[u8] =
+        b"\x1b\x1b\x1b[[[O;;~~uM<0123456789ABCDFHPQRSZam\x00\x7f\x80\xbf\xc3\xd9\xe2\xf0\xff";
+
+    // xorshift, to get the same inputs on every run
+    fn fuzz(runs: usize, mut f: impl FnMut(&[u8])) {
+        let mut seed: u64 = 0x2545f4914f6cdd1d;
+        for _ in 0..runs {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            let len = (seed % 12) as usize;
+            let bytes = (0..len)
+                .map(|i| {
+                    let r = (seed >> (i * 5)) as usize;
+                    if r % 7 == 0 {
+                        r as u8
+                    } else {
+                        ALPHABET[r % ALPHABET.len()]
+                    }
+                })
+                .collect::<Vec<u8>>();
+            f(&bytes);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(decode_ki(b"").unwrap_err(), DecodeError::Incomplete(vec![]));
+        assert_eq!(
+            decode_ki(b"\xff").unwrap_err(),
+            DecodeError::Unknown(vec![0xff])
+        );
+        assert_eq!(
+            decode_ki(b"\x1b[1;0A").unwrap_err(),
+            DecodeError::Unknown(b"\x1b[1;0A".to_vec())
+        );
+        assert_eq!(decode_ki(b"\x1b[3~").unwrap().char, Char::CC(CC::Delete));
+        assert_eq!(
+            decode_ki("لا".as_bytes()).unwrap().char,
+            Char::Grapheme("لا".to_string())
+        );
+        assert_eq!(
+            decode_utf8_string(&"لا".as_bytes()[..3]).unwrap_err(),
+            DecodeError::Incomplete(vec![0xd8])
+        );
+    }
+
+    #[test]
+    fn modifiers() {
+        assert_eq!(format!("{:?}", Modifiers(CONTROL | ALT)), "CONTROL_ALT");
+        assert_eq!(format!("{}", Modifiers(200)), "Modifiers(200)");
+        assert_eq!(Modifiers::from_or(&[CONTROL, SHIFT]), Some(Modifiers(10)));
+        assert_eq!(Modifiers::from_or(&[CONTROL, 0x20]), None);
+
+        let mut modifiers = Modifiers(SHIFT);
+        modifiers.or(ALT | 0x40);
+        assert_eq!(modifiers, Modifiers(SHIFT | ALT));
+    }
+
+    #[test]
+    fn no_panic() {
+        // every 1 and 2 bytes input
+        for b0 in 0..=255u8 {
+            _ = decode_ki(&[b0]);
+            for b1 in 0..=255u8 {
+                _ = decode_ki(&[b0, b1]);
+                _ = decode_ki_kai(vec![b0, b1]);
+            }
+        }
+
+        // every escape sequence of up to 5 bytes
+        for b2 in 0..=255u8 {
+            for b3 in 0..=255u8 {
+                _ = decode_ki(&[27, 91, b2, b3]);
+                _ = decode_ki(&[27, 91, b2, 59, b3]);
+                _ = decode_ki(&[27, 91, 49, 59, b2, b3]);
+                _ = decode_ki(&[27, 91, 49, b2, 59, b3, 126]);
+                _ = decode_ki(&[27, 91, 50, b2, 59, 49, b3, 126]);
+            }
+        }
+
+        fuzz(100_000, |bytes| {
+            _ = decode_ki(bytes);
+            _ = decode_ki_kai(bytes.to_vec());
+            _ = decode_kitty(bytes);
+            _ = decode_vt(bytes);
+            _ = decode_utf8_string(bytes);
+        });
+    }
+}
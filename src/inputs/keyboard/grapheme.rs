@@ -0,0 +1,235 @@
+// a trimmed down take on the unicode grapheme cluster boundary rules (UAX #29)
+// it covers what keyboards and input methods send in practice:
+// combining marks, variation selectors, emoji modifiers and tags,
+// zero width joiner emoji sequences and regional indicator (flag) pairs
+// on top of that, the arabic lam alef ligature is kept whole
+// since it is typed with a single key on arabic layouts
+
+const ZWJ: char = '\u{200d}';
+
+// combining and other extending code points
+// that never start a cluster but always join the previous char
+const EXTEND: &[(u32, u32)] = &[
+    (0x0300, 0x036f), // combining diacritical marks
+    (0x0483, 0x0489), // cyrillic
+    (0x0591, 0x05bd), // hebrew
+    (0x05bf, 0x05bf),
+    (0x05c1, 0x05c2),
+    (0x05c4, 0x05c5),
+    (0x05c7, 0x05c7),
+    (0x0610, 0x061a), // arabic
+    (0x064b, 0x065f),
+    (0x0670, 0x0670),
+    (0x06d6, 0x06dc),
+    (0x06df, 0x06e4),
+    (0x06e7, 0x06e8),
+    (0x06ea, 0x06ed),
+    (0x0900, 0x0903), // devanagari
+    (0x093a, 0x093c),
+    (0x093e, 0x094f),
+    (0x0951, 0x0957),
+    (0x0962, 0x0963),
+    (0x0981, 0x0983), // bengali
+    (0x09bc, 0x09bc),
+    (0x09be, 0x09cd),
+    (0x0e31, 0x0e31), // thai
+    (0x0e34, 0x0e3a),
+    (0x0e47, 0x0e4e),
+    (0x1ab0, 0x1aff),   // combining diacritical marks extended
+    (0x1dc0, 0x1dff),   // combining diacritical marks supplement
+    (0x200c, 0x200d),   // zero width non joiner and joiner
+    (0x20d0, 0x20ff),   // combining diacritical marks for symbols
+    (0x302a, 0x302f),   // ideographic tone marks
+    (0x3099, 0x309a),   // kana voicing marks
+    (0xfe00, 0xfe0f),   // variation selectors
+    (0xfe20, 0xfe2f),   // combining half marks
+    (0x1f3fb, 0x1f3ff), // emoji skin tone modifiers
+    (0xe0020, 0xe007f), // tags, used by subdivision flags
+    (0xe0100, 0xe01ef), // variation selectors supplement
+];
+
+// pictographic code points, the ones a zero width joiner glues together
+const PICTOGRAPHIC: &[(u32, u32)] = &[
+    (0x00a9, 0x00a9),
+    (0x00ae, 0x00ae),
+    (0x2190, 0x21ff),
+    (0x2300, 0x23ff),
+    (0x2600, 0x27bf),
+    (0x2b00, 0x2bff),
+    (0x1f000, 0x1faff),
+];
+
+const REGIONAL_INDICATOR: (u32, u32) = (0x1f1e6, 0x1f1ff);
+
+// code points that take 2 terminal columns; east asian wide and fullwidth chars and emoji
+const WIDE: &[(u32, u32)] = &[
+    (0x1100, 0x115f),   // hangul jamo
+    (0x231a, 0x231b),   // watch, hourglass
+    (0x2e80, 0x303e),   // cjk radicals, punctuation
+    (0x3041, 0x33ff),   // kana, cjk compatibility
+    (0x3400, 0x4dbf),   // cjk extension a
+    (0x4e00, 0x9fff),   // cjk unified ideographs
+    (0xa000, 0xa4cf),   // yi
+    (0xac00, 0xd7a3),   // hangul syllables
+    (0xf900, 0xfaff),   // cjk compatibility ideographs
+    (0xfe30, 0xfe4f),   // cjk compatibility forms
+    (0xff00, 0xff60),   // fullwidth forms
+    (0xffe0, 0xffe6),
+    (0x1f1e6, 0x1f1ff), // regional indicators, a flag pair
+    (0x1f300, 0x1f64f), // pictographs, emoticons
+    (0x1f680, 0x1f6ff), // transport and map symbols
+    (0x1f900, 0x1faff), // supplemental pictographs
+    (0x20000, 0x3fffd), // cjk extensions b and up
+];
+
+const LAM: char = '\u{644}';
+// alef, alef with madda above, alef with hamza above and alef with hamza below
+const ALEFS: &[char] = &['\u{627}', '\u{622}', '\u{623}', '\u{625}'];
+
+fn within(c: char, ranges: &[(u32, u32)]) -> bool {
+    let c = c as u32;
+
+    ranges.iter().any(|(start, end)| *start <= c && c <= *end)
+}
+
+fn is_extend(c: char) -> bool {
+    within(c, EXTEND)
+}
+
+fn is_regional(c: char) -> bool {
+    within(c, &[REGIONAL_INDICATOR])
+}
+
+fn is_pictographic(c: char) -> bool {
+    within(c, PICTOGRAPHIC)
+}
+
+/// returns the length, in bytes, of the first grapheme cluster of the given str
+/// 0 if the str is empty
+pub fn cluster_len(s: &str) -> usize {
+    let mut chars = s.char_indices();
+    let Some((_, first)) = chars.next() else {
+        return 0;
+    };
+
+    let mut prev = first;
+    // regional indicators pair up, a third one starts a new flag
+    let mut regionals = is_regional(first) as u8;
+    let mut len = first.len_utf8();
+
+    for (idx, c) in chars {
+        let joins = is_extend(c)
+            || (prev == ZWJ && is_pictographic(c))
+            || (is_regional(c) && regionals == 1)
+            || (prev == LAM && ALEFS.contains(&c) && idx == first.len_utf8());
+        if !joins {
+            break;
+        }
+
+        if is_regional(c) {
+            regionals += 1;
+        }
+        prev = c;
+        len = idx + c.len_utf8();
+    }
+
+    len
+}
+
+/// returns how many terminal columns the given grapheme cluster takes, 1 or 2
+/// 0 if the str is empty
+///
+/// a cluster is as wide as its first char,
+/// or 2 for a pictograph turned into an emoji by a variation selector, e.g., '❤\u{fe0f}'
+pub fn width(cluster: &str) -> usize {
+    let Some(first) = cluster.chars().next() else {
+        return 0;
+    };
+
+    match within(first, WIDE) || (is_pictographic(first) && cluster.contains('\u{fe0f}')) {
+        true => 2,
+        false => 1,
+    }
+}
+
+/// splits the given str into its grapheme clusters
+///
+/// # Examples
+/// ```
+/// assert_eq!(graphemes("e\u{301}👍🏽"), ["e\u{301}", "👍🏽"]);
+/// ```
+pub fn graphemes(s: &str) -> Vec<&str> {
+    let mut v = vec![];
+
+    let mut s = s;
+    while !s.is_empty() {
+        let len = cluster_len(s);
+        v.push(&s[..len]);
+        s = &s[len..];
+    }
+
+    v
+}
+
+// returns whether the cluster is certainly not over yet
+// i.e., it ends with a zero width joiner or a lone regional indicator
+// the lam is left out; the lam alef key sends both chars at once
+pub(crate) fn open_ended(cluster: &str) -> bool {
+    let Some(last) = cluster.chars().last() else {
+        return false;
+    };
+
+    last == ZWJ
+        || (is_regional(last) && cluster.chars().filter(|c| is_regional(*c)).count() % 2 == 1)
+}
+
+#[cfg(test)]
+mod test_grapheme {
+    use super::{cluster_len, graphemes, open_ended, width};
+
+    #[test]
+    fn clusters() {
+        // combining acute accent
+        assert_eq!(graphemes("e\u{301}a"), ["e\u{301}", "a"]);
+        // lam alef
+        assert_eq!(graphemes("لا"), ["لا"]);
+        assert_eq!(graphemes("لاب"), ["لا", "ب"]);
+        // arabic harakat
+        assert_eq!(graphemes("بَت"), ["بَ", "ت"]);
+        // family emoji; man zwj woman zwj girl
+        assert_eq!(
+            graphemes("👨\u{200d}👩\u{200d}👧!"),
+            ["👨\u{200d}👩\u{200d}👧", "!"]
+        );
+        // skin tone and variation selector
+        assert_eq!(graphemes("👍🏽❤\u{fe0f}"), ["👍🏽", "❤\u{fe0f}"]);
+        // flags pair up
+        assert_eq!(graphemes("🇯🇵🇫🇷🇩"), ["🇯🇵", "🇫🇷", "🇩"]);
+        // plain text stays one char per cluster
+        assert_eq!(graphemes("abc"), ["a", "b", "c"]);
+        assert_eq!(cluster_len(""), 0);
+    }
+
+    #[test]
+    fn widths() {
+        assert_eq!(width("a"), 1);
+        assert_eq!(width("e\u{301}"), 1);
+        assert_eq!(width("لا"), 1);
+        assert_eq!(width("漢"), 2);
+        assert_eq!(width("👨\u{200d}👩\u{200d}👧"), 2);
+        assert_eq!(width("🇯🇵"), 2);
+        assert_eq!(width("❤"), 1);
+        assert_eq!(width("❤\u{fe0f}"), 2);
+        assert_eq!(width(""), 0);
+    }
+
+    #[test]
+    fn open() {
+        assert!(open_ended("👨\u{200d}"));
+        assert!(open_ended("🇯"));
+        assert!(!open_ended("🇯🇵"));
+        assert!(!open_ended("ل"));
+        assert!(!open_ended("لا"));
+        assert!(!open_ended("a"));
+    }
+}
@@ -0,0 +1,289 @@
+use std::io::Write;
+
+use super::vt::fold_shift;
+use super::{Char, KbdEvent, KeyKind, Modifiers, ALT, CC, CONTROL, SHIFT, SUPER};
+
+// progressive enhancement flags, from https://sw.kovidgoyal.net/kitty/keyboard-protocol/
+/// report ctrl/alt key combinations and the escape key as unambiguous CSI u sequences
+pub const DISAMBIGUATE: u8 = 0b1;
+/// report key repeats and releases
+pub const REPORT_EVENTS: u8 = 0b10;
+/// report the shifted and base layout keys along with the key code
+pub const REPORT_ALTERNATES: u8 = 0b100;
+/// report all keys, text producing ones included, as CSI u sequences
+pub const REPORT_ALL_KEYS: u8 = 0b1000;
+/// report the text a key produces along with the key code
+pub const REPORT_TEXT: u8 = 0b10000;
+
+/// pushes the given kitty keyboard flags onto the terminal's flags stack
+/// e.g., DISAMBIGUATE | REPORT_EVENTS
+///
+/// terminals that don't support the protocol simply ignore this
+/// the flags must be popped again with disable_kitty_keyboard before the program exits
+pub fn enable_kitty_keyboard(writer: &mut impl Write, flags: u8) {
+    _ = writer.write(format!("\x1b[>{}u", flags).as_bytes());
+}
+
+/// pops the flags pushed by enable_kitty_keyboard
+/// restoring the keyboard mode that was active before
+pub fn disable_kitty_keyboard(writer: &mut impl Write) {
+    _ = writer.write(b"\x1b[<u");
+}
+
+/// decodes a kitty keyboard protocol sequence; ESC[code;modifiers:kind u
+/// along with the functional keys the protocol sends in legacy form; ESC[1;modifiers:kind A etc.
+///
+/// returns None if the bytes do not start with such a sequence
+/// only the first sequence is decoded, any bytes after it are ignored
+pub fn decode_kitty(bytes: &[u8]) -> Option<KbdEvent> {
+    let body = bytes.strip_prefix(b"\x1b[")?;
+    let end = body
+        .iter()
+        .position(|b| !(b.is_ascii_digit() || *b == b';' || *b == b':'))?;
+    let params = std::str::from_utf8(&body[..end]).ok()?;
+
+    // the key field can carry alternate keys, code:shifted:base
+    // the modifiers field can carry the event kind, modifiers:kind
+    // a third field holding the produced text may follow, it is not needed here
+    let mut fields = params.split(';');
+    let code = match fields.next().and_then(|f| f.split(':').next()) {
+        None | Some("") => 1,
+        Some(code) => code.parse::<u32>().ok()?,
+    };
+    let mut mods = fields.next().unwrap_or("").split(':');
+    let modifiers = match mods.next() {
+        None | Some("") => 1,
+        // the value is 1 + the modifier bits, never 0
+        Some(m) => m.parse::<u8>().ok().filter(|m| *m > 0)?,
+    };
+    let kind = match mods.next() {
+        None | Some("") | Some("1") => KeyKind::Press,
+        Some("2") => KeyKind::Repeat,
+        Some("3") => KeyKind::Release,
+        Some(_) => return None,
+    };
+
+    let char = match body[end] {
+        b'u' => from_code(code)?,
+        b'~' => from_tilde(code)?,
+        fin @ b'A'..=b'D' => Char::from_arrow_key(fin)?,
+        b'H' => Char::CC(CC::Home),
+        b'F' => Char::CC(CC::End),
+        // F3 is sent as ESC[13~, ESC[1;..R would clash with cursor position reports
+        fin @ (b'P' | b'Q' | b'S') => Char::from_fn_key3(fin)?,
+        _ => return None,
+    };
+
+    let ke = KbdEvent {
+        char,
+        modifiers: from_kitty_modifiers(modifiers),
+        kind,
+    };
+
+    // the F13 to F24 keys have their own code, only the legacy forms carry a shifted F1 to F12
+    Some(match body[end] {
+        b'u' => ke,
+        _ => fold_shift(ke),
+    })
+}
+
+// the key code is the unicode code point of the key, lowercase for letters
+// functional keys without a code point use the private use area
+// the ones with no CC counterpart, e.g., caps lock or the media keys, are not supported
+fn from_code(code: u32) -> Option<Char> {
+    Some(match code {
+        9 => Char::CC(CC::TAB),
+        13 => Char::CC(CC::CR),
+        27 => Char::CC(CC::ESC),
+        127 => Char::CC(CC::BS),
+        57344..=63743 => Char::CC(from_private_code(code)?),
+        0..=31 => return None,
+        code => Char::Char(char::from_u32(code)?),
+    })
+}
+
+fn from_private_code(code: u32) -> Option<CC> {
+    Some(match code {
+        57361 => CC::PrintScreen,
+        57363 => CC::Menu,
+        57376 => CC::F13,
+        57377 => CC::F14,
+        57378 => CC::F15,
+        57379 => CC::F16,
+        57380 => CC::F17,
+        57381 => CC::F18,
+        57382 => CC::F19,
+        57383 => CC::F20,
+        57384 => CC::F21,
+        57385 => CC::F22,
+        57386 => CC::F23,
+        57387 => CC::F24,
+        57399..=57416 => from_keypad_code(code - 57399)?,
+        57417 => CC::Left,
+        57418 => CC::Right,
+        57419 => CC::Up,
+        57420 => CC::Down,
+        57421 => CC::PageUp,
+        57422 => CC::PageDown,
+        57423 => CC::Home,
+        57424 => CC::End,
+        57425 => CC::Insert,
+        57426 => CC::Delete,
+        57427 => CC::KeypadBegin,
+        _ => return None,
+    })
+}
+
+// the keypad keys in kitty order; 0 to 9, decimal, divide, multiply, subtract, add, enter, equal, separator
+pub(super) fn from_keypad_code(idx: u32) -> Option<CC> {
+    Some(match idx {
+        0 => CC::Keypad0,
+        1 => CC::Keypad1,
+        2 => CC::Keypad2,
+        3 => CC::Keypad3,
+        4 => CC::Keypad4,
+        5 => CC::Keypad5,
+        6 => CC::Keypad6,
+        7 => CC::Keypad7,
+        8 => CC::Keypad8,
+        9 => CC::Keypad9,
+        10 => CC::KeypadDecimal,
+        11 => CC::KeypadDivide,
+        12 => CC::KeypadMultiply,
+        13 => CC::KeypadSubtract,
+        14 => CC::KeypadAdd,
+        15 => CC::KeypadEnter,
+        16 => CC::KeypadEqual,
+        17 => CC::KeypadSeparator,
+        _ => return None,
+    })
+}
+
+// vt220 and xterm numbering, rxvt and the linux console send their extra keys the same way
+// 1 and 4 are the linux console home and end, 7 and 8 the rxvt ones
+// 25 to 34 are the vt220 F13 to F20, 29 is also the menu key
+pub(super) fn from_tilde(code: u32) -> Option<Char> {
+    Some(Char::CC(match code {
+        1 => CC::Home,
+        4 => CC::End,
+        2 => CC::Insert,
+        3 => CC::Delete,
+        5 => CC::PageUp,
+        6 => CC::PageDown,
+        7 => CC::Home,
+        8 => CC::End,
+        11 => CC::F1,
+        12 => CC::F2,
+        13 => CC::F3,
+        14 => CC::F4,
+        15 => CC::F5,
+        17 => CC::F6,
+        18 => CC::F7,
+        19 => CC::F8,
+        20 => CC::F9,
+        21 => CC::F10,
+        23 => CC::F11,
+        24 => CC::F12,
+        25 => CC::F13,
+        26 => CC::F14,
+        28 => CC::F15,
+        29 => CC::Menu,
+        31 => CC::F17,
+        32 => CC::F18,
+        33 => CC::F19,
+        34 => CC::F20,
+        _ => return None,
+    }))
+}
+
+// kitty sends 1 + shift(1) | alt(2) | ctrl(4) | super(8) | hyper | meta | caps lock | num lock
+// the last 4 have no Modifiers counterpart and get dropped
+fn from_kitty_modifiers(byte: u8) -> Modifiers {
+    let byte = byte.saturating_sub(1);
+
+    let mut mods = 0;
+    if byte & 1 != 0 {
+        mods |= SHIFT;
+    }
+    if byte & 2 != 0 {
+        mods |= ALT;
+    }
+    if byte & 4 != 0 {
+        mods |= CONTROL;
+    }
+    if byte & 8 != 0 {
+        mods |= SUPER;
+    }
+
+    Modifiers(mods)
+}
+
+// the reverse of from_kitty_modifiers, xterm uses the same values
+pub(super) fn to_kitty_modifiers(mods: &Modifiers) -> u8 {
+    let mut byte = 0;
+    if mods.0 & SHIFT != 0 {
+        byte |= 1;
+    }
+    if mods.0 & ALT != 0 {
+        byte |= 2;
+    }
+    if mods.0 & CONTROL != 0 {
+        byte |= 4;
+    }
+    if mods.0 & SUPER != 0 {
+        byte |= 8;
+    }
+
+    byte + 1
+}
+
+#[cfg(test)]
+mod test_kitty {
+    use super::decode_kitty;
+    use crate::inputs::keyboard::{Char, KeyKind, Modifiers, CC};
+
+    #[test]
+    fn csi_u() {
+        // ctrl + i is no longer a tab
+        let ke = decode_kitty(b"\x1b[105;5u").unwrap();
+        assert_eq!(ke.char, Char::Char('i'));
+        assert_eq!(ke.modifiers, Modifiers(2));
+        assert_eq!(ke.kind, KeyKind::Press);
+
+        let ke = decode_kitty(b"\x1b[13u").unwrap();
+        assert_eq!(ke.char, Char::CC(CC::CR));
+        assert_eq!(ke.modifiers, Modifiers(0));
+
+        let ke = decode_kitty(b"\x1b[97:65;2:3u").unwrap();
+        assert_eq!(ke.char, Char::Char('a'));
+        assert_eq!(ke.modifiers, Modifiers(8));
+        assert_eq!(ke.kind, KeyKind::Release);
+
+        let ke = decode_kitty("\x1b[1604;1:2u".as_bytes()).unwrap();
+        assert_eq!(ke.char, Char::Char('ل'));
+        assert_eq!(ke.kind, KeyKind::Repeat);
+    }
+
+    #[test]
+    fn functional() {
+        let ke = decode_kitty(b"\x1b[1;1:3A").unwrap();
+        assert_eq!(ke.char, Char::CC(CC::Up));
+        assert_eq!(ke.kind, KeyKind::Release);
+
+        let ke = decode_kitty(b"\x1b[3;13~").unwrap();
+        assert_eq!(ke.char, Char::CC(CC::Delete));
+        assert_eq!(ke.modifiers, Modifiers(3));
+
+        let ke = decode_kitty(b"\x1b[A").unwrap();
+        assert_eq!(ke.char, Char::CC(CC::Up));
+        assert_eq!(ke.kind, KeyKind::Press);
+    }
+
+    #[test]
+    fn not_kitty() {
+        assert!(decode_kitty(b"a").is_none());
+        assert!(decode_kitty(b"\x1b[").is_none());
+        assert!(decode_kitty(b"\x1b[Z").is_none());
+        assert!(decode_kitty(b"\x1b[57441u").is_none());
+    }
+}
@@ -0,0 +1,466 @@
+use std::io::Write;
+
+use super::kitty::{from_keypad_code, from_tilde, to_kitty_modifiers};
+use super::{Char, KbdEvent, KeyKind, Modifiers, ALT, CC, CONTROL, NONE, SHIFT};
+
+const ESC: u8 = 0x1b;
+
+// the SS3 final bytes of the keypad keys in application keypad mode, in kitty order
+// 0 to 9, decimal, divide, multiply, subtract, add, enter, equal, separator
+const KEYPAD: &[u8; 18] = b"pqrstuvwxynojmkMXl";
+
+/// switches the keypad to application keypad mode (DECKPAM)
+/// its keys then send their own SS3 sequences instead of digits and operators
+pub fn enable_keypad_mode(writer: &mut impl Write) {
+    _ = writer.write(b"\x1b=");
+}
+
+/// switches the keypad back to numeric keypad mode (DECKPNM)
+pub fn disable_keypad_mode(writer: &mut impl Write) {
+    _ = writer.write(b"\x1b>");
+}
+
+/// decodes the key sequences that don't follow the xterm CSI layout
+/// - SS3 keys; ESC O final, e.g., F1 to F4, the application cursor mode arrows
+///   and the application keypad mode keys
+/// - the linux console F1 to F5; ESC [ [ A to ESC [ [ E
+/// - the rxvt shift + arrows; ESC [ a to ESC [ d, and ctrl + arrows; ESC O a to ESC O d
+/// - the rxvt modified keys; ESC [ code $ for shift, ^ for ctrl and @ for ctrl + shift
+///
+/// returns None if the bytes are not exactly one of those sequences
+pub fn decode_vt(bytes: &[u8]) -> Option<KbdEvent> {
+    let (char, modifiers) = match bytes {
+        [ESC, b'O', fin] => from_ss3(*fin)?,
+        [ESC, b'[', b'[', fin @ b'A'..=b'E'] => (Char::CC(fn_key(fin - b'A' + 1)?), NONE),
+        [ESC, b'[', fin @ b'a'..=b'd'] => (Char::from_arrow_key(fin - 32)?, SHIFT),
+        [ESC, b'[', code @ .., fin @ (b'$' | b'^' | b'@')]
+            if !code.is_empty() && code.iter().all(u8::is_ascii_digit) =>
+        {
+            let code = std::str::from_utf8(code).ok()?.parse::<u32>().ok()?;
+            let modifiers = match fin {
+                b'$' => SHIFT,
+                b'^' => CONTROL,
+                _ => CONTROL | SHIFT,
+            };
+
+            (from_tilde(code)?, modifiers)
+        }
+        _ => return None,
+    };
+
+    Some(fold_shift(KbdEvent {
+        char,
+        modifiers: Modifiers(modifiers),
+        kind: KeyKind::Press,
+    }))
+}
+
+fn from_ss3(fin: u8) -> Option<(Char, u8)> {
+    Some(match fin {
+        b'A'..=b'D' => (Char::from_arrow_key(fin)?, NONE),
+        b'a'..=b'd' => (Char::from_arrow_key(fin - 32)?, CONTROL),
+        b'P'..=b'S' => (Char::from_fn_key3(fin)?, NONE),
+        b'H' => (Char::CC(CC::Home), NONE),
+        b'F' => (Char::CC(CC::End), NONE),
+        b'E' => (Char::CC(CC::KeypadBegin), NONE),
+        _ => {
+            let idx = KEYPAD.iter().position(|b| *b == fin)?;
+
+            (Char::CC(from_keypad_code(idx as u32)?), NONE)
+        }
+    })
+}
+
+// the F key with the given number, from 1 to 24
+fn fn_key(n: u8) -> Option<CC> {
+    Some(match n {
+        1 => CC::F1,
+        2 => CC::F2,
+        3 => CC::F3,
+        4 => CC::F4,
+        5 => CC::F5,
+        6 => CC::F6,
+        7 => CC::F7,
+        8 => CC::F8,
+        9 => CC::F9,
+        10 => CC::F10,
+        11 => CC::F11,
+        12 => CC::F12,
+        13 => CC::F13,
+        14 => CC::F14,
+        15 => CC::F15,
+        16 => CC::F16,
+        17 => CC::F17,
+        18 => CC::F18,
+        19 => CC::F19,
+        20 => CC::F20,
+        21 => CC::F21,
+        22 => CC::F22,
+        23 => CC::F23,
+        24 => CC::F24,
+        _ => return None,
+    })
+}
+
+fn fn_number(cc: &CC) -> Option<u8> {
+    (1..=24).find(|n| fn_key(*n).as_ref() == Some(cc))
+}
+
+// terminals send shift + F1..F12 as F13..F24
+// the shift gets folded into the key, the other modifiers are kept
+pub(super) fn fold_shift(mut ke: KbdEvent) -> KbdEvent {
+    if ke.modifiers.0 & SHIFT == 0 {
+        return ke;
+    }
+
+    if let Char::CC(cc) = &ke.char {
+        if let Some(n @ 1..=12) = fn_number(cc) {
+            ke.char = Char::CC(fn_key(n + 12).unwrap_or(CC::F24));
+            ke.modifiers.0 &= !SHIFT;
+        }
+    }
+
+    ke
+}
+
+/// encodes a keyboard event into the bytes an xterm like terminal sends for it
+/// the reverse of decode_ki;
+/// keys, modifiers and event kinds the legacy sequences can't express get the kitty protocol form
+///
+/// >[!NOTE] a Char::Grapheme is encoded as its text, its modifiers are dropped
+///
+/// # Examples
+/// ```
+/// let ke = KbdEvent {
+///     char: Char::CC(CC::Up),
+///     modifiers: Modifiers(CONTROL),
+///     kind: KeyKind::Press,
+/// };
+/// assert_eq!(encode_ki(&ke), b"\x1b[1;5A");
+/// ```
+pub fn encode_ki(ke: &KbdEvent) -> Vec<u8> {
+    let cc = match &ke.char {
+        Char::Grapheme(s) => return s.as_bytes().to_vec(),
+        Char::Char(c) => return encode_char(*c, &ke.modifiers, ke.kind),
+        Char::CC(cc) => cc,
+    };
+    let mods = to_kitty_modifiers(&ke.modifiers);
+    let plain = mods == 1 && ke.kind == KeyKind::Press;
+
+    // ascii control chars
+    let byte = match cc {
+        CC::TAB => Some(9),
+        CC::CR => Some(13),
+        CC::ESC => Some(27),
+        CC::BS => Some(127),
+        _ => None,
+    };
+    if let Some(byte) = byte {
+        return match (ke.modifiers.0, ke.kind) {
+            (NONE, KeyKind::Press) => vec![byte],
+            // alt + esc would read as 2 escape keys
+            (ALT, KeyKind::Press) if byte != ESC => vec![ESC, byte],
+            (SHIFT, KeyKind::Press) if byte == 9 => b"\x1b[Z".to_vec(),
+            _ => csi(byte as u32, mods, ke.kind, b'u'),
+        };
+    }
+
+    if let Some(n) = fn_number(cc) {
+        // F13 to F24 are sent as shift + F1 to F12
+        let (n, mods) = match n {
+            13..=24 => (
+                n - 12,
+                to_kitty_modifiers(&Modifiers(ke.modifiers.0 | SHIFT)),
+            ),
+            _ => (n, mods),
+        };
+
+        return match n {
+            1..=4 if mods == 1 && ke.kind == KeyKind::Press => vec![ESC, b'O', b'P' + n - 1],
+            // ESC[1;..R would clash with cursor position reports
+            3 => csi(13, mods, ke.kind, b'~'),
+            1..=4 => csi(1, mods, ke.kind, b'P' + n - 1),
+            _ => csi(
+                [15, 17, 18, 19, 20, 21, 23, 24][n as usize - 5],
+                mods,
+                ke.kind,
+                b'~',
+            ),
+        };
+    }
+
+    if let Some(idx) = (0..18).find(|idx| from_keypad_code(*idx).as_ref() == Some(cc)) {
+        return match plain {
+            true => vec![ESC, b'O', KEYPAD[idx as usize]],
+            false => csi(57399 + idx, mods, ke.kind, b'u'),
+        };
+    }
+
+    let (code, fin) = match cc {
+        CC::Up => (1, b'A'),
+        CC::Down => (1, b'B'),
+        CC::Right => (1, b'C'),
+        CC::Left => (1, b'D'),
+        CC::Home => (1, b'H'),
+        CC::End => (1, b'F'),
+        CC::Insert => (2, b'~'),
+        CC::Delete => (3, b'~'),
+        CC::PageUp => (5, b'~'),
+        CC::PageDown => (6, b'~'),
+        CC::Menu => (29, b'~'),
+        CC::PrintScreen => (57361, b'u'),
+        CC::KeypadBegin if plain => return b"\x1bOE".to_vec(),
+        // the keypad begin key, every other key got encoded above
+        _ => (57427, b'u'),
+    };
+
+    csi(code, mods, ke.kind, fin)
+}
+
+fn encode_char(c: char, modifiers: &Modifiers, kind: KeyKind) -> Vec<u8> {
+    let mods = to_kitty_modifiers(modifiers);
+    if kind != KeyKind::Press {
+        return csi(c as u32, mods, kind, b'u');
+    }
+
+    // ctrl + i and ctrl + m are the tab and enter bytes
+    let ctrl = c.is_ascii_lowercase() && c != 'i' && c != 'm';
+    // alt + these would start a CSI, SS3, OSC or DCS sequence
+    let alt = !['[', 'O', ']', 'P'].contains(&c);
+
+    match modifiers.0 {
+        NONE => c.to_string().into_bytes(),
+        ALT if alt => format!("\x1b{}", c).into_bytes(),
+        CONTROL if ctrl => vec![c as u8 - 96],
+        m if m == CONTROL | ALT && ctrl => vec![ESC, c as u8 - 96],
+        _ => csi(c as u32, mods, kind, b'u'),
+    }
+}
+
+// ESC [ code ; modifiers : kind final
+// the defaults, code 1, modifiers 1 and kind press, get left out
+fn csi(code: u32, mods: u8, kind: KeyKind, fin: u8) -> Vec<u8> {
+    let kind = match kind {
+        KeyKind::Press => "",
+        KeyKind::Repeat => ":2",
+        KeyKind::Release => ":3",
+    };
+
+    let mut seq = match (mods, kind) {
+        (1, "") if code == 1 => "\x1b[".to_string(),
+        (1, "") => format!("\x1b[{}", code),
+        _ => format!("\x1b[{};{}{}", code, mods, kind),
+    };
+    seq.push(fin as char);
+
+    seq.into_bytes()
+}
+
+#[cfg(test)]
+mod test_vt {
+    use super::{decode_vt, encode_ki};
+    use crate::inputs::keyboard::{decode_ki, Char, KbdEvent, KeyKind, Modifiers, CC};
+    use crate::inputs::parser::InputParser;
+    use crate::inputs::Interaction;
+
+    // decodes the bytes the way the event reader does
+    fn parse(bytes: &[u8]) -> KbdEvent {
+        let mut parser = InputParser::new();
+        let mut events = parser.feed(bytes);
+        events.extend(parser.finish());
+        assert_eq!(events.len(), 1, "{:?}", bytes);
+
+        match events.remove(0).event {
+            Interaction::KbdEvent(ke) => ke,
+            e => panic!("not a keyboard event: {:?}", e),
+        }
+    }
+
+    fn ke(char: Char, mods: u8, kind: KeyKind) -> KbdEvent {
+        KbdEvent {
+            char,
+            modifiers: Modifiers(mods),
+            kind,
+        }
+    }
+
+    #[test]
+    fn terminals() {
+        let cases: &[(&[u8], CC, u8)] = &[
+            // xterm
+            (b"\x1bOP", CC::F1, 0),
+            (b"\x1b[1;2P", CC::F13, 0),
+            (b"\x1b[1;6Q", CC::F14, 2),
+            (b"\x1b[13;2~", CC::F15, 0),
+            (b"\x1b[1;2S", CC::F16, 0),
+            (b"\x1b[15;2~", CC::F17, 0),
+            (b"\x1b[24;2~", CC::F24, 0),
+            (b"\x1b[24;5~", CC::F12, 2),
+            (b"\x1b[3~", CC::Delete, 0),
+            (b"\x1b[3;5~", CC::Delete, 2),
+            (b"\x1b[29~", CC::Menu, 0),
+            (b"\x1bOH", CC::Home, 0),
+            // rxvt
+            (b"\x1b[11~", CC::F1, 0),
+            (b"\x1b[14~", CC::F4, 0),
+            (b"\x1b[25~", CC::F13, 0),
+            (b"\x1b[26~", CC::F14, 0),
+            (b"\x1b[28~", CC::F15, 0),
+            (b"\x1b[31~", CC::F17, 0),
+            (b"\x1b[34~", CC::F20, 0),
+            (b"\x1b[23$", CC::F23, 0),
+            (b"\x1b[24^", CC::F12, 2),
+            (b"\x1b[3$", CC::Delete, 8),
+            (b"\x1b[5^", CC::PageUp, 2),
+            (b"\x1b[7@", CC::Home, 10),
+            (b"\x1b[8~", CC::End, 0),
+            (b"\x1b[a", CC::Up, 8),
+            (b"\x1bOd", CC::Left, 2),
+            // linux console
+            (b"\x1b[[A", CC::F1, 0),
+            (b"\x1b[[E", CC::F5, 0),
+            (b"\x1b[1~", CC::Home, 0),
+            (b"\x1b[4~", CC::End, 0),
+            // application keypad mode
+            (b"\x1bOp", CC::Keypad0, 0),
+            (b"\x1bOy", CC::Keypad9, 0),
+            (b"\x1bOn", CC::KeypadDecimal, 0),
+            (b"\x1bOo", CC::KeypadDivide, 0),
+            (b"\x1bOj", CC::KeypadMultiply, 0),
+            (b"\x1bOm", CC::KeypadSubtract, 0),
+            (b"\x1bOk", CC::KeypadAdd, 0),
+            (b"\x1bOM", CC::KeypadEnter, 0),
+            (b"\x1bOX", CC::KeypadEqual, 0),
+            (b"\x1bOl", CC::KeypadSeparator, 0),
+            (b"\x1bOE", CC::KeypadBegin, 0),
+            // kitty
+            (b"\x1b[57376u", CC::F13, 0),
+            (b"\x1b[57387;5u", CC::F24, 2),
+            (b"\x1b[57361u", CC::PrintScreen, 0),
+            (b"\x1b[57363u", CC::Menu, 0),
+            (b"\x1b[57399u", CC::Keypad0, 0),
+            (b"\x1b[57414u", CC::KeypadEnter, 0),
+            (b"\x1b[57426u", CC::Delete, 0),
+        ];
+
+        for (bytes, cc, mods) in cases {
+            let ke = parse(bytes);
+            assert_eq!(ke.char, Char::CC(*cc), "{:?}", bytes);
+            assert_eq!(ke.modifiers, Modifiers(*mods), "{:?}", bytes);
+
+            let ke = decode_ki(bytes).unwrap();
+            assert_eq!(ke.char, Char::CC(*cc), "{:?}", bytes);
+        }
+
+        assert!(decode_vt(b"\x1b[$").is_none());
+        assert!(decode_vt(b"\x1b[[F").is_none());
+        assert!(decode_vt(b"\x1bOz").is_none());
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut keys = vec![
+            ke(Char::Char('a'), 0, KeyKind::Press),
+            ke(Char::Char('ل'), 0, KeyKind::Press),
+            ke(Char::Char('x'), 4, KeyKind::Press),
+            ke(Char::Char('['), 4, KeyKind::Press),
+            ke(Char::Char('c'), 2, KeyKind::Press),
+            ke(Char::Char('i'), 2, KeyKind::Press),
+            ke(Char::Char('w'), 6, KeyKind::Press),
+            ke(Char::Char('A'), 10, KeyKind::Press),
+            ke(Char::Char('q'), 0, KeyKind::Release),
+            ke(Char::Grapheme("e\u{301}".to_string()), 0, KeyKind::Press),
+            ke(Char::CC(CC::TAB), 8, KeyKind::Press),
+            ke(Char::CC(CC::CR), 4, KeyKind::Press),
+            ke(Char::CC(CC::ESC), 4, KeyKind::Press),
+            ke(Char::CC(CC::BS), 2, KeyKind::Repeat),
+            ke(Char::CC(CC::Up), 0, KeyKind::Release),
+            ke(Char::CC(CC::F3), 0, KeyKind::Repeat),
+        ];
+        // every other key, alone and with every modifiers combination
+        let ccs = [
+            CC::BS,
+            CC::TAB,
+            CC::CR,
+            CC::Up,
+            CC::Down,
+            CC::Right,
+            CC::Left,
+            CC::Insert,
+            CC::Delete,
+            CC::Home,
+            CC::End,
+            CC::PageUp,
+            CC::PageDown,
+            CC::F1,
+            CC::F2,
+            CC::F3,
+            CC::F4,
+            CC::F5,
+            CC::F6,
+            CC::F7,
+            CC::F8,
+            CC::F9,
+            CC::F10,
+            CC::F11,
+            CC::F12,
+            CC::F13,
+            CC::F14,
+            CC::F15,
+            CC::F16,
+            CC::F17,
+            CC::F18,
+            CC::F19,
+            CC::F20,
+            CC::F21,
+            CC::F22,
+            CC::F23,
+            CC::F24,
+            CC::Menu,
+            CC::PrintScreen,
+            CC::Keypad0,
+            CC::Keypad1,
+            CC::Keypad2,
+            CC::Keypad3,
+            CC::Keypad4,
+            CC::Keypad5,
+            CC::Keypad6,
+            CC::Keypad7,
+            CC::Keypad8,
+            CC::Keypad9,
+            CC::KeypadDecimal,
+            CC::KeypadDivide,
+            CC::KeypadMultiply,
+            CC::KeypadSubtract,
+            CC::KeypadAdd,
+            CC::KeypadEnter,
+            CC::KeypadEqual,
+            CC::KeypadSeparator,
+            CC::KeypadBegin,
+        ];
+        for cc in ccs {
+            for mods in 0..16 {
+                // shift + an F key is another F key
+                if super::fn_number(&cc).is_some() && mods & 8 != 0 {
+                    continue;
+                }
+                keys.push(ke(Char::CC(cc), mods, KeyKind::Press));
+            }
+        }
+
+        for key in keys {
+            let bytes = encode_ki(&key);
+            let ke = parse(&bytes);
+            assert_eq!(ke.char, key.char, "{:?}", bytes);
+            assert_eq!(ke.modifiers, key.modifiers, "{:?}", bytes);
+            assert_eq!(ke.kind, key.kind, "{:?}", bytes);
+        }
+
+        let up = ke(Char::CC(CC::Up), 2, KeyKind::Press);
+        assert_eq!(encode_ki(&up), b"\x1b[1;5A");
+        let f13 = ke(Char::CC(CC::F13), 0, KeyKind::Press);
+        assert_eq!(encode_ki(&f13), b"\x1b[1;2P");
+        let pad = ke(Char::CC(CC::KeypadEnter), 0, KeyKind::Press);
+        assert_eq!(encode_ki(&pad), b"\x1bOM");
+    }
+}
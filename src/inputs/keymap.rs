@@ -0,0 +1,667 @@
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use super::keyboard::grapheme::cluster_len;
+use super::keyboard::{Char, KbdEvent, KeyKind, Modifiers, ALT, CC, CONTROL, NONE, SHIFT, SUPER};
+
+/// the error returned when a key or a chord fails to parse
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyParseError {
+    /// the string holds no key at all
+    Empty,
+    /// the modifier name before a - or a + is not known
+    UnknownModifier(String),
+    /// the key name is neither a known key nor a single character
+    UnknownKey(String),
+}
+
+/// a sequence of keys pressed one after the other, e.g., "C-x C-s"
+#[derive(Debug, Clone)]
+pub struct Chord(pub Vec<KbdEvent>);
+
+// key names, the first one is the one used when formatting
+const NAMES: &[(CC, &[&str])] = &[
+    (CC::ESC, &["Esc", "Escape"]),
+    (CC::CR, &["Enter", "Return", "CR", "Ret"]),
+    (CC::TAB, &["Tab"]),
+    (CC::BS, &["Backspace", "BS"]),
+    (CC::Up, &["Up"]),
+    (CC::Down, &["Down"]),
+    (CC::Right, &["Right"]),
+    (CC::Left, &["Left"]),
+    (CC::Insert, &["Insert", "Ins"]),
+    (CC::Delete, &["Delete", "Del"]),
+    (CC::Home, &["Home"]),
+    (CC::End, &["End"]),
+    (CC::PageUp, &["PageUp", "PgUp"]),
+    (CC::PageDown, &["PageDown", "PgDn"]),
+    (CC::F1, &["F1"]),
+    (CC::F2, &["F2"]),
+    (CC::F3, &["F3"]),
+    (CC::F4, &["F4"]),
+    (CC::F5, &["F5"]),
+    (CC::F6, &["F6"]),
+    (CC::F7, &["F7"]),
+    (CC::F8, &["F8"]),
+    (CC::F9, &["F9"]),
+    (CC::F10, &["F10"]),
+    (CC::F11, &["F11"]),
+    (CC::F12, &["F12"]),
+    (CC::F13, &["F13"]),
+    (CC::F14, &["F14"]),
+    (CC::F15, &["F15"]),
+    (CC::F16, &["F16"]),
+    (CC::F17, &["F17"]),
+    (CC::F18, &["F18"]),
+    (CC::F19, &["F19"]),
+    (CC::F20, &["F20"]),
+    (CC::F21, &["F21"]),
+    (CC::F22, &["F22"]),
+    (CC::F23, &["F23"]),
+    (CC::F24, &["F24"]),
+    (CC::Menu, &["Menu"]),
+    (CC::PrintScreen, &["PrintScreen", "Print"]),
+    (CC::Keypad0, &["Kp0"]),
+    (CC::Keypad1, &["Kp1"]),
+    (CC::Keypad2, &["Kp2"]),
+    (CC::Keypad3, &["Kp3"]),
+    (CC::Keypad4, &["Kp4"]),
+    (CC::Keypad5, &["Kp5"]),
+    (CC::Keypad6, &["Kp6"]),
+    (CC::Keypad7, &["Kp7"]),
+    (CC::Keypad8, &["Kp8"]),
+    (CC::Keypad9, &["Kp9"]),
+    (CC::KeypadDecimal, &["KpDecimal"]),
+    (CC::KeypadDivide, &["KpDivide"]),
+    (CC::KeypadMultiply, &["KpMultiply"]),
+    (CC::KeypadSubtract, &["KpSubtract"]),
+    (CC::KeypadAdd, &["KpAdd"]),
+    (CC::KeypadEnter, &["KpEnter"]),
+    (CC::KeypadEqual, &["KpEqual"]),
+    (CC::KeypadSeparator, &["KpSeparator"]),
+    (CC::KeypadBegin, &["KpBegin"]),
+];
+
+// modifier prefixes, emacs style first; C-M-S-s-x
+const MODIFIERS: &[(u8, &[&str])] = &[
+    (CONTROL, &["C", "ctrl", "control"]),
+    (ALT, &["M", "A", "alt", "meta", "opt", "option"]),
+    (SHIFT, &["S", "shift"]),
+    (SUPER, &["s", "super", "cmd", "win"]),
+];
+
+// the single letter names are case sensitive, S is shift and s is super
+fn modifier(name: &str) -> Option<u8> {
+    MODIFIERS.iter().find_map(|(m, names)| {
+        names
+            .iter()
+            .any(|n| match n.len() {
+                1 => *n == name,
+                _ => n.eq_ignore_ascii_case(name),
+            })
+            .then_some(*m)
+    })
+}
+
+fn key(name: &str) -> Option<Char> {
+    // a single character; a, -, <, ل or a whole grapheme cluster like 'لا'
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (None, _) => return None,
+        (Some(c), None) => return Some(Char::Char(c)),
+        _ if cluster_len(name) == name.len() => return Some(Char::Grapheme(name.to_string())),
+        _ => (),
+    }
+
+    // vim style names
+    match name.to_ascii_lowercase().as_str() {
+        "space" | "spc" => return Some(Char::Char(' ')),
+        "lt" => return Some(Char::Char('<')),
+        _ => (),
+    }
+
+    NAMES.iter().find_map(|(cc, names)| {
+        names
+            .iter()
+            .any(|n| n.eq_ignore_ascii_case(name))
+            .then_some(Char::CC(*cc))
+    })
+}
+
+/// parses a key with its modifiers
+///
+/// accepts the emacs style; "C-x", "M-S-<F5>", the plus style; "ctrl+alt+Delete"
+/// and the vim style; "<C-x>", "<Esc>".
+/// modifier and key names are case insensitive, except for single letters
+///
+/// # Examples
+/// ```
+/// let ke: KbdEvent = "alt+shift+F5".parse().unwrap();
+/// assert_eq!(ke.char, Char::CC(CC::F5));
+/// assert_eq!(ke.to_string(), "M-S-<F5>");
+/// ```
+impl FromStr for KbdEvent {
+    type Err = KeyParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rest = unwrap_angles(s.trim());
+        if rest.is_empty() {
+            return Err(KeyParseError::Empty);
+        }
+
+        let mut mods = NONE;
+        // a separator that is the first or last char belongs to the key; "-", "C--", "ctrl++"
+        while let Some(idx) = rest.find(['-', '+']) {
+            if idx == 0 || idx + 1 == rest.len() {
+                break;
+            }
+            let name = &rest[..idx];
+            mods |=
+                modifier(name).ok_or_else(|| KeyParseError::UnknownModifier(name.to_string()))?;
+            rest = &rest[idx + 1..];
+        }
+
+        let name = unwrap_angles(rest);
+        let char = key(name).ok_or_else(|| KeyParseError::UnknownKey(name.to_string()))?;
+
+        Ok(KbdEvent {
+            char,
+            modifiers: Modifiers(mods),
+            kind: KeyKind::Press,
+        })
+    }
+}
+
+// <Esc> -> Esc, a lone < or <> stays as is
+fn unwrap_angles(s: &str) -> &str {
+    match s.strip_prefix('<').and_then(|s| s.strip_suffix('>')) {
+        Some(inner) if !inner.is_empty() => inner,
+        _ => s,
+    }
+}
+
+/// formats the key the emacs way; C-x, M-S-<F5>, <Esc>
+/// the output parses back into the same key
+impl Display for KbdEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (m, names) in MODIFIERS {
+            if self.modifiers.0 & m != 0 {
+                write!(f, "{}-", names[0])?;
+            }
+        }
+
+        match &self.char {
+            Char::Char(' ') => write!(f, "<Space>"),
+            Char::Char(c) => write!(f, "{}", c),
+            Char::Grapheme(s) => write!(f, "{}", s),
+            Char::CC(cc) => {
+                let name = NAMES
+                    .iter()
+                    .find(|(n, _)| n == cc)
+                    .map_or("?", |(_, names)| names[0]);
+
+                write!(f, "<{}>", name)
+            }
+        }
+    }
+}
+
+/// parses space separated keys, e.g., "C-x C-s" or "g g"
+impl FromStr for Chord {
+    type Err = KeyParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let keys = s
+            .split_whitespace()
+            .map(KbdEvent::from_str)
+            .collect::<Result<Vec<KbdEvent>, KeyParseError>>()?;
+        if keys.is_empty() {
+            return Err(KeyParseError::Empty);
+        }
+
+        Ok(Self(keys))
+    }
+}
+
+impl Display for Chord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (idx, ke) in self.0.iter().enumerate() {
+            if idx > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", ke)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// what a key fed to a Keymap resolved to
+#[derive(Debug)]
+pub enum Resolved<'a, A> {
+    /// the keys completed a bound chord
+    Action(&'a A),
+    /// the keys are the start of some chords, more keys are needed
+    Pending,
+    /// the keys are a bound chord and the start of longer chords at the same time
+    /// the action runs if the chord timeout expires before another key comes, see Keymap::flush
+    Ambiguous(&'a A),
+    /// the keys are not bound to anything, they are given back to be handled some other way
+    Unbound(Vec<KbdEvent>),
+}
+
+/// maps keys and chords of keys to actions
+///
+/// # Examples
+/// ```
+/// let mut keymap = Keymap::new();
+/// keymap.bind("C-x C-s", Action::Save).unwrap();
+/// keymap.bind("C-x C-c", Action::Quit).unwrap();
+///
+/// loop {
+///     for ie in parser.feed(bytes) {
+///         if let Interaction::KbdEvent(ke) = ie.event {
+///             match keymap.feed(ke) {
+///                 Resolved::Action(action) => run(action),
+///                 Resolved::Unbound(keys) => insert(keys),
+///                 _ => (),
+///             }
+///         }
+///     }
+///     while let Some(resolved) = keymap.flush() {
+///         match resolved {
+///             Resolved::Action(action) => run(action),
+///             Resolved::Unbound(keys) => insert(keys),
+///             _ => (),
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Keymap<A> {
+    bindings: Vec<(Vec<KbdEvent>, A)>,
+    /// the keys of the chord being typed
+    pending: Vec<KbdEvent>,
+    /// how long a started chord waits for its next key
+    chord_timeout: Duration,
+    /// when the last pending key came
+    since: Option<Instant>,
+    /// keys that broke a chord, resolved one by one by feed and flush
+    deferred: VecDeque<KbdEvent>,
+}
+
+impl<A> Default for Keymap<A> {
+    fn default() -> Self {
+        Self {
+            bindings: vec![],
+            pending: vec![],
+            chord_timeout: Duration::from_secs(1),
+            since: None,
+            deferred: VecDeque::new(),
+        }
+    }
+}
+
+impl<A> Keymap<A> {
+    /// creates a new empty Keymap with a 1 second chord timeout
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// sets how long a started chord waits for its next key
+    pub fn chord_timeout(mut self, chord_timeout: Duration) -> Self {
+        self.chord_timeout = chord_timeout;
+        self
+    }
+
+    /// binds the given chord to the given action
+    /// replaces the action the chord was bound to before, if any
+    ///
+    /// # Errors
+    ///
+    /// returns an error if the chord fails to parse
+    pub fn bind(&mut self, chord: &str, action: A) -> Result<(), KeyParseError> {
+        let chord = chord.parse::<Chord>()?.0;
+        match self
+            .bindings
+            .iter_mut()
+            .find(|(keys, _)| same_keys(keys, &chord))
+        {
+            Some(binding) => binding.1 = action,
+            None => self.bindings.push((chord, action)),
+        }
+
+        Ok(())
+    }
+
+    /// removes the binding of the given chord and returns its action
+    ///
+    /// # Errors
+    ///
+    /// returns an error if the chord fails to parse
+    pub fn unbind(&mut self, chord: &str) -> Result<Option<A>, KeyParseError> {
+        let chord = chord.parse::<Chord>()?.0;
+        let idx = self
+            .bindings
+            .iter()
+            .position(|(keys, _)| same_keys(keys, &chord));
+
+        Ok(idx.map(|idx| self.bindings.remove(idx).1))
+    }
+
+    /// feeds the next key to the keymap
+    ///
+    /// key releases never match anything, they are given back as unbound
+    /// without breaking the chord being typed
+    ///
+    /// a key that breaks the chord being typed does not get lost;
+    /// if the chord is bound on its own it resolves to its action first,
+    /// and the key is then resolved from scratch by the next feed or flush.
+    /// an unbound chord is dropped and the key is resolved right away if it is bound alone,
+    /// e.g., C-x C-c runs the C-c action when only C-x C-s starts with C-x
+    ///
+    /// a key fed while broken chord keys are waiting goes behind them,
+    /// feed resolves the oldest waiting key instead
+    pub fn feed(&mut self, ke: KbdEvent) -> Resolved<'_, A> {
+        if let Some(front) = self.deferred.pop_front() {
+            self.deferred.push_back(ke);
+            return self.resolve(front);
+        }
+
+        self.resolve(ke)
+    }
+
+    fn resolve(&mut self, ke: KbdEvent) -> Resolved<'_, A> {
+        if ke.kind == KeyKind::Release {
+            return Resolved::Unbound(vec![ke]);
+        }
+
+        self.pending.push(ke);
+        let exact = self
+            .bindings
+            .iter()
+            .position(|(keys, _)| same_keys(keys, &self.pending));
+        let longer = self.bindings.iter().any(|(keys, _)| {
+            keys.len() > self.pending.len() && same_keys(&keys[..self.pending.len()], &self.pending)
+        });
+
+        match (exact, longer) {
+            (Some(idx), false) => {
+                self.reset();
+                Resolved::Action(&self.bindings[idx].1)
+            }
+            (Some(idx), true) => {
+                self.since = Some(Instant::now());
+                Resolved::Ambiguous(&self.bindings[idx].1)
+            }
+            (None, true) => {
+                self.since = Some(Instant::now());
+                Resolved::Pending
+            }
+            (None, false) => {
+                self.since = None;
+                let mut prefix = std::mem::take(&mut self.pending);
+                let Some(ke) = prefix.pop().filter(|_| !prefix.is_empty()) else {
+                    return Resolved::Unbound(prefix);
+                };
+
+                // the key broke a chord; it gets resolved on its own if it means anything alone
+                let action = self
+                    .bindings
+                    .iter()
+                    .position(|(keys, _)| same_keys(keys, &prefix));
+                let alone = self
+                    .bindings
+                    .iter()
+                    .any(|(keys, _)| same_key(&keys[0], &ke));
+                match (action, alone) {
+                    (Some(idx), _) => {
+                        self.deferred.push_front(ke);
+                        Resolved::Action(&self.bindings[idx].1)
+                    }
+                    (None, true) => self.resolve(ke),
+                    (None, false) => {
+                        prefix.push(ke);
+                        Resolved::Unbound(prefix)
+                    }
+                }
+            }
+        }
+    }
+
+    /// resolves the next key that broke a chord, see feed,
+    /// or the chord being typed if the chord timeout has expired
+    /// an ambiguous chord resolves to its action, an unfinished one is given back as unbound
+    /// returns None if there is nothing to resolve yet
+    pub fn flush(&mut self) -> Option<Resolved<'_, A>> {
+        if let Some(ke) = self.deferred.pop_front() {
+            return Some(self.resolve(ke));
+        }
+
+        match self.since {
+            Some(since) if since.elapsed() >= self.chord_timeout => (),
+            _ => return None,
+        }
+
+        let pending = std::mem::take(&mut self.pending);
+        self.reset();
+
+        Some(
+            match self
+                .bindings
+                .iter()
+                .find(|(keys, _)| same_keys(keys, &pending))
+            {
+                Some((_, action)) => Resolved::Action(action),
+                None => Resolved::Unbound(pending),
+            },
+        )
+    }
+
+    /// returns how long is left until flush resolves the chord being typed
+    /// zero if keys that broke a chord are waiting for flush
+    /// or None if no chord is being typed
+    pub fn timeout(&self) -> Option<Duration> {
+        if !self.deferred.is_empty() {
+            return Some(Duration::ZERO);
+        }
+
+        self.since
+            .map(|since| self.chord_timeout.saturating_sub(since.elapsed()))
+    }
+
+    /// returns the keys of the chord being typed
+    pub fn pending(&self) -> &[KbdEvent] {
+        &self.pending
+    }
+
+    fn reset(&mut self) {
+        self.pending.clear();
+        self.since = None;
+    }
+}
+
+fn same_keys(a: &[KbdEvent], b: &[KbdEvent]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same_key(a, b))
+}
+
+// shift + a letter is the same key as the capital letter,
+// legacy terminals send the latter while the kitty protocol can send the former
+fn same_key(a: &KbdEvent, b: &KbdEvent) -> bool {
+    fn normalize(ke: &KbdEvent) -> (Option<char>, u8) {
+        match ke.char {
+            Char::Char(c) if ke.modifiers.0 & SHIFT != 0 && c.is_lowercase() => {
+                (c.to_uppercase().next(), ke.modifiers.0 & !SHIFT)
+            }
+            Char::Char(c) => (Some(c), ke.modifiers.0),
+            _ => (None, ke.modifiers.0),
+        }
+    }
+
+    match (&a.char, &b.char) {
+        (Char::Char(_), Char::Char(_)) => normalize(a) == normalize(b),
+        (a_char, b_char) => a_char == b_char && a.modifiers == b.modifiers,
+    }
+}
+
+#[cfg(test)]
+mod test_keymap {
+    use super::{Chord, KeyParseError, Keymap, Resolved};
+    use crate::inputs::keyboard::{Char, KbdEvent, KeyKind, Modifiers, CC};
+    use std::time::Duration;
+
+    fn key(s: &str) -> KbdEvent {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        let ke = key("C-x");
+        assert_eq!(ke.char, Char::Char('x'));
+        assert_eq!(ke.modifiers, Modifiers(2));
+
+        let ke = key("alt+shift+F5");
+        assert_eq!(ke.char, Char::CC(CC::F5));
+        assert_eq!(ke.modifiers, Modifiers(12));
+
+        assert_eq!(key("<Esc>").char, Char::CC(CC::ESC));
+        assert_eq!(key("<C-Enter>").modifiers, Modifiers(2));
+        assert_eq!(key("s-S-<pgup>").modifiers, Modifiers(9));
+        assert_eq!(key("C--").char, Char::Char('-'));
+        assert_eq!(key("ctrl++").char, Char::Char('+'));
+        assert_eq!(key("<").char, Char::Char('<'));
+        assert_eq!(key("M-<Space>").char, Char::Char(' '));
+        assert_eq!(key("لا").char, Char::Grapheme("لا".to_string()));
+
+        assert_eq!("".parse::<KbdEvent>().unwrap_err(), KeyParseError::Empty);
+        assert_eq!(
+            "hyper+x".parse::<KbdEvent>().unwrap_err(),
+            KeyParseError::UnknownModifier("hyper".to_string())
+        );
+        assert_eq!(
+            "C-foo".parse::<KbdEvent>().unwrap_err(),
+            KeyParseError::UnknownKey("foo".to_string())
+        );
+        assert!(" ".parse::<Chord>().is_err());
+    }
+
+    #[test]
+    fn display() {
+        for s in [
+            "C-x",
+            "M-S-<F5>",
+            "<Esc>",
+            "C-M-S-s-<Delete>",
+            "C--",
+            "M-<Space>",
+            "<Kp7>",
+        ] {
+            assert_eq!(key(s).to_string(), s);
+        }
+        assert_eq!(key("alt+shift+F5").to_string(), "M-S-<F5>");
+
+        let chord: Chord = "C-x   ctrl+s".parse().unwrap();
+        assert_eq!(chord.to_string(), "C-x C-s");
+    }
+
+    #[test]
+    fn chords() {
+        let mut keymap = Keymap::new().chord_timeout(Duration::from_secs(60));
+        keymap.bind("C-x C-s", "save").unwrap();
+        keymap.bind("C-x C-c", "quit").unwrap();
+        keymap.bind("g", "top?").unwrap();
+        keymap.bind("g g", "top").unwrap();
+        keymap.bind("S-a", "append").unwrap();
+        keymap.bind("g", "go").unwrap();
+
+        assert!(matches!(keymap.feed(key("C-x")), Resolved::Pending));
+        assert_eq!(keymap.pending().len(), 1);
+        assert!(matches!(keymap.feed(key("C-s")), Resolved::Action(&"save")));
+        assert!(keymap.timeout().is_none());
+
+        // a release does not break the chord
+        assert!(matches!(keymap.feed(key("C-x")), Resolved::Pending));
+        let mut release = key("C-x");
+        release.kind = KeyKind::Release;
+        assert!(matches!(keymap.feed(release), Resolved::Unbound(_)));
+        assert!(matches!(keymap.feed(key("C-c")), Resolved::Action(&"quit")));
+
+        // a broken chord gives all its keys back
+        keymap.feed(key("C-x"));
+        match keymap.feed(key("q")) {
+            Resolved::Unbound(keys) => assert_eq!(keys.len(), 2),
+            _ => panic!("expected the keys back"),
+        }
+
+        // the legacy form of shift + a
+        assert!(matches!(keymap.feed(key("A")), Resolved::Action(&"append")));
+
+        assert!(matches!(keymap.feed(key("g")), Resolved::Ambiguous(&"go")));
+        assert!(keymap.flush().is_none());
+        assert!(matches!(keymap.feed(key("g")), Resolved::Action(&"top")));
+
+        assert_eq!(keymap.unbind("g g").unwrap(), Some("top"));
+        assert!(matches!(keymap.feed(key("g")), Resolved::Action(&"go")));
+    }
+
+    #[test]
+    fn chord_timeout() {
+        let mut keymap = Keymap::new().chord_timeout(Duration::ZERO);
+        keymap.bind("g", "go").unwrap();
+        keymap.bind("g g", "top").unwrap();
+        keymap.bind("d d", "delete").unwrap();
+
+        assert!(matches!(keymap.feed(key("g")), Resolved::Ambiguous(_)));
+        assert!(matches!(keymap.flush(), Some(Resolved::Action(&"go"))));
+        assert!(keymap.pending().is_empty());
+
+        assert!(matches!(keymap.feed(key("d")), Resolved::Pending));
+        match keymap.flush() {
+            Some(Resolved::Unbound(keys)) => assert_eq!(keys[0].char, Char::Char('d')),
+            _ => panic!("expected the key back"),
+        }
+        assert!(keymap.flush().is_none());
+    }
+
+    #[test]
+    fn broken_chord() {
+        let mut keymap = Keymap::new().chord_timeout(Duration::from_secs(60));
+        keymap.bind("g", "go").unwrap();
+        keymap.bind("g g", "top").unwrap();
+        keymap.bind("j", "down").unwrap();
+        keymap.bind("d d", "delete").unwrap();
+
+        // the ambiguous chord runs its action, then the key that broke it is resolved alone
+        assert!(matches!(keymap.feed(key("g")), Resolved::Ambiguous(&"go")));
+        assert!(matches!(keymap.feed(key("j")), Resolved::Action(&"go")));
+        assert_eq!(keymap.timeout(), Some(Duration::ZERO));
+        assert!(matches!(keymap.flush(), Some(Resolved::Action(&"down"))));
+        assert!(keymap.flush().is_none());
+
+        // an unbound prefix is dropped, the key that broke it resolves right away
+        assert!(matches!(keymap.feed(key("d")), Resolved::Pending));
+        assert!(matches!(keymap.feed(key("g")), Resolved::Ambiguous(&"go")));
+        assert!(matches!(keymap.feed(key("g")), Resolved::Action(&"top")));
+
+        // a key fed while another one waits goes behind it, feed resolves the waiting one
+        assert!(matches!(keymap.feed(key("g")), Resolved::Ambiguous(&"go")));
+        assert!(matches!(keymap.feed(key("j")), Resolved::Action(&"go")));
+        assert!(matches!(keymap.feed(key("j")), Resolved::Action(&"down")));
+        assert!(matches!(keymap.flush(), Some(Resolved::Action(&"down"))));
+        assert!(keymap.flush().is_none());
+        assert!(keymap.pending().is_empty());
+    }
+
+    #[test]
+    fn broken_chord_without_flush() {
+        let mut keymap = Keymap::new();
+        keymap.bind("C-c", "quit").unwrap();
+        keymap.bind("C-x C-s", "save").unwrap();
+
+        assert!(matches!(keymap.feed(key("C-x")), Resolved::Pending));
+        assert!(matches!(keymap.feed(key("C-c")), Resolved::Action(&"quit")));
+        assert!(keymap.timeout().is_none());
+    }
+}
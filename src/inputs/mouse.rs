@@ -1,14 +1,23 @@
-use std::io::{StdoutLock, Write};
+/// synthesises drags and multi clicks out of raw mouse events
+pub mod gestures;
 
-use crate::inputs::keyboard::Modifiers;
+pub use gestures::GestureTracker;
+
+use std::io::Write;
+
+use crate::inputs::keyboard::{Modifiers, ALT, CONTROL, SHIFT};
+use crate::inputs::DecodeError;
 
 /// Mouse event struct
 /// contains the mouse gesture, modifiers and the [x,y] coordinates of the cursor
 #[derive(Default, Debug, Clone)]
 pub struct MouseEvent {
-    gesture: Gesture,
-    modifiers: Modifiers,
-    position: [u8; 2],
+    /// what the mouse did
+    pub gesture: Gesture,
+    /// the keyboard modifiers held during the gesture
+    pub modifiers: Modifiers,
+    /// the [x, y] coordinates of the cursor, starting from [0, 0]
+    pub position: [u16; 2],
 }
 
 /// Mouse gesture enum
@@ -17,15 +26,34 @@ pub struct MouseEvent {
 pub enum Gesture {
     /// simply moving in any direction
     /// provides the current coordinates of the cursor
-    Move(u8, u8),
+    Move(u16, u16),
     /// 1 finger touchpad press
     LeftPress,
     /// release the mouse/touchpad
+    /// the legacy encoding can not tell which button got released
     Release,
     /// 2 fingers touchpad press
     RightPress,
     /// 3 fingers touchpad press
     WheelePress,
+    /// left button release, only reported in SGR mode
+    LeftRelease,
+    /// right button release, only reported in SGR mode
+    RightRelease,
+    /// wheele button release, only reported in SGR mode
+    WheeleRelease,
+    /// moving while a button is held down
+    /// only reported in the Drag and Motion tracking modes
+    /// raw events have from == to, a GestureTracker fills in where the drag started
+    Drag {
+        button: Button,
+        from: [u16; 2],
+        to: [u16; 2],
+    },
+    /// second press of the same button in a short time, synthesised by a GestureTracker
+    DoubleClick(Button),
+    /// third press of the same button in a short time, synthesised by a GestureTracker
+    TripleClick(Button),
     /// touchpad 2 fingers up
     WheeleUp,
     /// touchpad 2 fingers down
@@ -43,69 +71,196 @@ pub enum Gesture {
     None,
 }
 
-/// decodes input bytes into a vec of mouse events
-pub fn decode_mi(bytes: &[u8]) -> Vec<MouseEvent> {
-    let mut rem = bytes.len();
-    assert_eq!(rem % 6, 0);
+/// the mouse buttons
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Button {
+    Left,
+    Right,
+    Wheele,
+}
+
+/// which mouse events the terminal reports
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Tracking {
+    /// only button presses, releases and the wheele
+    Click,
+    /// Click plus movement while a button is held down
+    Drag,
+    /// Drag plus any movement, even with no button held down
+    #[default]
+    Motion,
+}
 
+impl Tracking {
+    // the dec private mode that turns on this tracking mode
+    fn mode(&self) -> &'static [u8] {
+        match self {
+            Self::Click => b"\x1b[?1000h",
+            Self::Drag => b"\x1b[?1002h",
+            Self::Motion => b"\x1b[?1003h",
+        }
+    }
+}
+
+/// decodes input bytes into a vec of mouse events
+/// understands both the SGR (1006) encoding; ESC[<b;x;yM or ESC[<b;x;ym
+/// and the legacy X10 encoding; ESC[M followed by 3 bytes
+///
+/// # Errors
+///
+/// returns DecodeError::Incomplete if the bytes stop in the middle of a mouse sequence
+/// and DecodeError::Unknown if they hold anything else than mouse sequences
+pub fn decode_mi(bytes: &[u8]) -> Result<Vec<MouseEvent>, DecodeError> {
     let mut v: Vec<MouseEvent> = Vec::new();
 
-    let mut me = MouseEvent::default();
-    let mut bytes = bytes.into_iter();
+    let mut bytes = bytes;
+    while !bytes.is_empty() {
+        let mut me = MouseEvent::default();
+
+        if bytes.starts_with(b"\x1b[<") {
+            let end = bytes
+                .iter()
+                .position(|b| *b == b'M' || *b == b'm')
+                .ok_or_else(|| DecodeError::Incomplete(bytes.to_vec()))?;
+            decode_sgr(&bytes[3..end], bytes[end], &mut me)
+                .ok_or_else(|| DecodeError::Unknown(bytes[..=end].to_vec()))?;
+            bytes = &bytes[end + 1..];
+        } else if bytes.len() < 6 && b"\x1b[M".starts_with(&bytes[..bytes.len().min(3)]) {
+            return Err(DecodeError::Incomplete(bytes.to_vec()));
+        } else {
+            decode_6_bytes(&bytes[..6.min(bytes.len())], &mut me)
+                .ok_or_else(|| DecodeError::Unknown(bytes.to_vec()))?;
+            bytes = &bytes[6..];
+        }
+
+        v.push(me);
+    }
+
+    Ok(v)
+}
 
-    while rem != 0 {
-        let eve = (0..6)
-            .into_iter()
-            .map(|_| bytes.next())
-            .filter(|o| o.is_some())
-            .map(|o| *o.unwrap())
-            .collect::<Vec<u8>>();
+// SGR sequences carry the button code, x and y as decimal parameters
+// the final byte is 'M' for a press/motion and 'm' for a release
+// coordinates start from 1
+fn decode_sgr(params: &[u8], fin: u8, me: &mut MouseEvent) -> Option<()> {
+    let params = std::str::from_utf8(params)
+        .ok()?
+        .split(';')
+        .map(|p| p.parse::<u16>().ok())
+        .collect::<Option<Vec<u16>>>()?;
+    let [code, x, y] = params[..] else {
+        return None;
+    };
+    let [x, y] = [x.saturating_sub(1), y.saturating_sub(1)];
 
-        decode_6_bytes(eve, &mut me);
+    me.modifiers = sgr_modifiers(code);
+    me.gesture = sgr_gesture(code, fin == b'm', x, y);
+    me.position = [x, y];
 
-        v.push(me.clone());
+    Some(())
+}
 
-        rem -= 6;
+// the low 2 bits are the button, 4 is shift, 8 is alt, 16 is ctrl,
+// 32 means motion and 64 means wheele
+fn sgr_gesture(code: u16, release: bool, x: u16, y: u16) -> Gesture {
+    if code & 64 != 0 {
+        // same cycle of 4 as the legacy encoding
+        match code % 4 {
+            0 => Gesture::WheeleDown,
+            1 => Gesture::WheeleUp,
+            2 => Gesture::WheeleRight,
+            _ => Gesture::WheeleLeft,
+        }
+    } else if code & 32 != 0 {
+        match button(code) {
+            Some(button) => Gesture::Drag {
+                button,
+                from: [x, y],
+                to: [x, y],
+            },
+            None => Gesture::Move(x, y),
+        }
+    } else {
+        match (code % 4, release) {
+            (0, false) => Gesture::LeftPress,
+            (1, false) => Gesture::WheelePress,
+            (2, false) => Gesture::RightPress,
+            (0, true) => Gesture::LeftRelease,
+            (1, true) => Gesture::WheeleRelease,
+            (2, true) => Gesture::RightRelease,
+            _ => Gesture::Release,
+        }
     }
+}
 
-    v
+// the button held down in a motion report, 3 means no button
+fn button(code: u16) -> Option<Button> {
+    match code % 4 {
+        0 => Some(Button::Left),
+        1 => Some(Button::Wheele),
+        2 => Some(Button::Right),
+        _ => None,
+    }
+}
+
+fn sgr_modifiers(code: u16) -> Modifiers {
+    let mut mods = 0;
+    if code & 4 != 0 {
+        mods |= SHIFT;
+    }
+    if code & 8 != 0 {
+        mods |= ALT;
+    }
+    if code & 16 != 0 {
+        mods |= CONTROL;
+    }
+
+    Modifiers(mods)
 }
 
 // th fourth byte; bytes[3] denotes the action being taken as well as the modifiers
 // the last 2 bytes are for cursor position (x, y)
 // the cursor position returned always start from 33 so should remove 33 from both x and y
 // to get the correct position
-fn decode_6_bytes(bytes: Vec<u8>, me: &mut MouseEvent) {
-    // assert mouse escape sequence
-    assert_eq!(bytes[0], 27);
-    assert_eq!(bytes[1], 91);
-    assert_eq!(bytes[2], 77);
-    me.modifiers = mouse_modifiers(bytes[3]);
-    me.gesture = mouse_gesture(bytes[3], bytes[4], bytes[5]);
-    me.position = [bytes[4] - 33, bytes[5] - 33];
-}
-
-fn mouse_gesture(byte: u8, bx: u8, by: u8) -> Gesture {
-    match byte {
+fn decode_6_bytes(bytes: &[u8], me: &mut MouseEvent) -> Option<()> {
+    // mouse escape sequence
+    let [27, 91, 77, b, bx, by] = bytes[..] else {
+        return None;
+    };
+    if bx < 33 || by < 33 {
+        return None;
+    }
+    me.modifiers = mouse_modifiers(b)?;
+    me.gesture = mouse_gesture(b, bx, by)?;
+    me.position = [bx as u16 - 33, by as u16 - 33];
+
+    Some(())
+}
+
+fn mouse_gesture(byte: u8, bx: u8, by: u8) -> Option<Gesture> {
+    Some(match byte {
         35 | 43 | 51 | 59 => Gesture::Release,
         67 | 71 | 75 | 83 | 87 | 79 | 91 | 95 => Gesture::None,
+        // motion with a button held down, reported in the Drag and Motion tracking modes
+        64..=95 => sgr_gesture(byte as u16 - 32, false, bx as u16 - 33, by as u16 - 33),
         32 | 40 | 48 | 56 => Gesture::LeftPress,
         33 | 41 | 49 | 57 => Gesture::WheelePress,
         34 | 50 | 52 | 48 => Gesture::RightPress,
-        96..=123 => match byte % 4 {
+        96..=127 => match byte % 4 {
             0 => Gesture::WheeleDown,
             1 => Gesture::WheeleUp,
             2 => Gesture::WheeleRight,
-            3 => Gesture::WheeleLeft,
-            _ => unreachable!("these codes rejuvenate in cycles of 4"),
+            _ => Gesture::WheeleLeft,
         },
-        _ => unreachable!("all possible cases have been handled"),
-    }
+        // the remaining presses, shift + click among them
+        32..=63 => sgr_gesture(byte as u16 - 32, false, bx as u16 - 33, by as u16 - 33),
+        _ => return None,
+    })
 }
 
-fn mouse_modifiers(byte: u8) -> Modifiers {
+fn mouse_modifiers(byte: u8) -> Option<Modifiers> {
     // shift + 3 finger/wheele click does a paste
-    match byte {
+    Some(match byte {
         67 | 32..=35 | 96..=99 => Modifiers(0),
         83 | 48..=51 | 112..=115 => Modifiers(2),
         71 | 100..=103 => Modifiers(8),
@@ -114,21 +269,120 @@ fn mouse_modifiers(byte: u8) -> Modifiers {
         91 | 56..=58 | 120..=123 => Modifiers(6), // ctrl + alt
         79 | 108..=111 => Modifiers(12),          // shift + alt
         95 | 124..=127 => Modifiers(15),
-        _ => unreachable!("all possible cases have been handled"),
-    }
+        32..=127 => sgr_modifiers(byte as u16 - 32),
+        _ => return None,
+    })
 }
 
 /// enables raw mouse input reading
 /// when this function is called, the prgram can start reading raw mouse input events
 /// which can then be used in the program
-pub fn enable_mouse_input(writer: &mut StdoutLock) {
+///
+/// the tracking mode decides which events get reported;
+/// Tracking::Motion reports every cursor move, which can flood the event loop
+pub fn enable_mouse_input(writer: &mut impl Write, tracking: Tracking) {
     // NOTE: the following line enables the terminal to receive mouse events
-    _ = writer.write(b"\x1b[?1003h");
+    _ = writer.write(tracking.mode());
+    // 1006 opts into the SGR encoding, which has no 223 columns/rows limit
+    // and tells apart which button got released
+    _ = writer.write(b"\x1b[?1006h");
 }
 
 /// disables raw mouse input reading
 /// makes the mouse unusable in the program
 /// once this is called, the terminal emulator will start detecting mouse input events again
-pub fn disable_mouse_input(writer: &mut StdoutLock) {
-    _ = writer.write(b"\x1b[?1003l");
+pub fn disable_mouse_input(writer: &mut impl Write) {
+    // all tracking modes are turned off, whichever one was enabled
+    _ = writer.write(b"\x1b[?1006l\x1b[?1003l\x1b[?1002l\x1b[?1000l");
+}
+
+#[cfg(test)]
+mod test_mouse {
+    use super::{decode_mi, Button, Gesture};
+    use crate::inputs::keyboard::Modifiers;
+    use crate::inputs::DecodeError;
+
+    #[test]
+    fn sgr() {
+        let me = decode_mi(b"\x1b[<0;301;48M").unwrap().remove(0);
+        assert!(matches!(me.gesture, Gesture::LeftPress));
+        assert_eq!(me.position, [300, 47]);
+
+        let me = decode_mi(b"\x1b[<2;1;1m").unwrap().remove(0);
+        assert!(matches!(me.gesture, Gesture::RightRelease));
+        assert_eq!(me.position, [0, 0]);
+
+        let me = decode_mi(b"\x1b[<16;5;5m").unwrap().remove(0);
+        assert!(matches!(me.gesture, Gesture::LeftRelease));
+        assert_eq!(me.modifiers, Modifiers(2));
+
+        let me = decode_mi(b"\x1b[<35;1000;2M").unwrap().remove(0);
+        assert!(matches!(me.gesture, Gesture::Move(999, 1)));
+
+        let me = decode_mi(b"\x1b[<34;7;9M").unwrap().remove(0);
+        assert!(matches!(
+            me.gesture,
+            Gesture::Drag {
+                button: Button::Right,
+                from: [6, 8],
+                to: [6, 8]
+            }
+        ));
+    }
+
+    #[test]
+    fn legacy_drag() {
+        let me = decode_mi(b"\x1b[M@%&").unwrap().remove(0);
+        assert!(matches!(
+            me.gesture,
+            Gesture::Drag {
+                button: Button::Left,
+                from: [4, 5],
+                to: [4, 5]
+            }
+        ));
+    }
+
+    #[test]
+    fn batched() {
+        let v = decode_mi(b"\x1b[<64;3;3M\x1b[M !!\x1b[<1;3;3m").unwrap();
+        assert_eq!(v.len(), 3);
+        assert!(matches!(v[1].gesture, Gesture::LeftPress));
+        assert_eq!(v[1].position, [0, 0]);
+        assert!(matches!(v[2].gesture, Gesture::WheeleRelease));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            decode_mi(b"\x1b[<0;1").unwrap_err(),
+            DecodeError::Incomplete(b"\x1b[<0;1".to_vec())
+        );
+        assert_eq!(
+            decode_mi(b"\x1b[M !").unwrap_err(),
+            DecodeError::Incomplete(b"\x1b[M !".to_vec())
+        );
+        assert_eq!(
+            decode_mi(b"\x1b[<0;1;1;1M").unwrap_err(),
+            DecodeError::Unknown(b"\x1b[<0;1;1;1M".to_vec())
+        );
+        assert!(decode_mi(b"hello").is_err());
+    }
+
+    #[test]
+    fn no_panic() {
+        // every legacy sequence
+        for b in 0..=255u8 {
+            for x in 0..=255u8 {
+                _ = decode_mi(&[27, 91, 77, b, x, 40]);
+            }
+        }
+
+        for code in ["", "0", "-1", "99999", "a", "64;", "3"] {
+            for pos in ["", ";", "1;1", ";1", "70000;3", "1;1;1", "0;0"] {
+                _ = decode_mi(format!("\x1b[<{};{}M", code, pos).as_bytes());
+                _ = decode_mi(format!("\x1b[<{}{}m", code, pos).as_bytes());
+            }
+        }
+    }
 }
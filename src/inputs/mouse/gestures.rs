@@ -0,0 +1,209 @@
+use std::time::{Duration, SystemTime};
+
+use super::{Button, Gesture, MouseEvent};
+
+/// turns raw mouse events into richer gestures
+///
+/// a press of the same button, close enough in time and space to the previous one,
+/// becomes a Gesture::DoubleClick then a Gesture::TripleClick.
+/// drags get the position the button was pressed at as their from field
+///
+/// # Examples
+/// ```
+/// let mut tracker = GestureTracker::new().click_interval(Duration::from_millis(300));
+///
+/// loop {
+///     let ie = event(read(&mut reader, &mut buf), &mut ws);
+///     if let Interaction::MouseEvent(me) = ie.event {
+///         match tracker.track(me, ie.time).gesture {
+///             Gesture::DoubleClick(Button::Left) => select_word(),
+///             Gesture::Drag { from, to, .. } => select_area(from, to),
+///             _ => (),
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct GestureTracker {
+    /// the longest time between 2 presses that still counts as a multi click
+    interval: Duration,
+    /// the furthest 2 presses can be from each other, in cells, and still count as a multi click
+    distance: u16,
+    /// the button currently held down and where it got pressed
+    held: Option<(Button, [u16; 2])>,
+    /// the last press; its button, position, time and how many clicks it made so far
+    last: Option<(Button, [u16; 2], SystemTime, u8)>,
+}
+
+impl Default for GestureTracker {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_millis(400),
+            distance: 1,
+            held: None,
+            last: None,
+        }
+    }
+}
+
+impl GestureTracker {
+    /// creates a new GestureTracker
+    /// with a 400 milliseconds click interval and a 1 cell click distance
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// sets the longest time between 2 presses that still counts as a multi click
+    pub fn click_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// sets the furthest 2 presses can be from each other, in cells, and still count as a multi click
+    pub fn click_distance(mut self, distance: u16) -> Self {
+        self.distance = distance;
+        self
+    }
+
+    /// feeds a raw mouse event to the tracker, along with the time it happened at
+    /// returns the event with its gesture upgraded if it completed a drag or a multi click
+    pub fn track(&mut self, mut me: MouseEvent, time: SystemTime) -> MouseEvent {
+        match me.gesture {
+            Gesture::LeftPress | Gesture::RightPress | Gesture::WheelePress => {
+                let button = pressed(&me.gesture);
+                self.held = Some((button, me.position));
+
+                let clicks = match self.last {
+                    Some((b, pos, at, n))
+                        if b == button
+                            && n < 3
+                            && self.near(pos, me.position)
+                            && time.duration_since(at).is_ok_and(|d| d <= self.interval) =>
+                    {
+                        n + 1
+                    }
+                    _ => 1,
+                };
+                self.last = Some((button, me.position, time, clicks));
+
+                match clicks {
+                    2 => me.gesture = Gesture::DoubleClick(button),
+                    3 => me.gesture = Gesture::TripleClick(button),
+                    _ => (),
+                }
+            }
+            Gesture::Drag { button, to, .. } => {
+                let from = match self.held {
+                    Some((b, from)) if b == button => from,
+                    _ => {
+                        // the press happened before tracking started
+                        self.held = Some((button, to));
+                        to
+                    }
+                };
+                // dragging away breaks the multi click chain
+                if !self.near(from, to) {
+                    self.last = None;
+                }
+                me.gesture = Gesture::Drag { button, from, to };
+            }
+            Gesture::LeftRelease
+            | Gesture::RightRelease
+            | Gesture::WheeleRelease
+            | Gesture::Release => self.held = None,
+            _ => (),
+        }
+
+        me
+    }
+
+    fn near(&self, a: [u16; 2], b: [u16; 2]) -> bool {
+        a[0].abs_diff(b[0]) <= self.distance && a[1].abs_diff(b[1]) <= self.distance
+    }
+}
+
+// the button of a press gesture
+fn pressed(gesture: &Gesture) -> Button {
+    match gesture {
+        Gesture::RightPress => Button::Right,
+        Gesture::WheelePress => Button::Wheele,
+        _ => Button::Left,
+    }
+}
+
+#[cfg(test)]
+mod test_gestures {
+    use super::GestureTracker;
+    use crate::inputs::mouse::{decode_mi, Button, Gesture};
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn multi_click() {
+        let mut tracker = GestureTracker::new();
+        let t = SystemTime::now();
+        let ms = Duration::from_millis;
+
+        let g = |tracker: &mut GestureTracker, bytes: &[u8], time| {
+            tracker
+                .track(decode_mi(bytes).unwrap().remove(0), time)
+                .gesture
+        };
+
+        assert!(matches!(
+            g(&mut tracker, b"\x1b[<0;5;5M", t),
+            Gesture::LeftPress
+        ));
+        g(&mut tracker, b"\x1b[<0;5;5m", t + ms(50));
+        assert!(matches!(
+            g(&mut tracker, b"\x1b[<0;6;5M", t + ms(100)),
+            Gesture::DoubleClick(Button::Left)
+        ));
+        assert!(matches!(
+            g(&mut tracker, b"\x1b[<0;6;5M", t + ms(200)),
+            Gesture::TripleClick(Button::Left)
+        ));
+        // a fourth press starts over
+        assert!(matches!(
+            g(&mut tracker, b"\x1b[<0;6;5M", t + ms(300)),
+            Gesture::LeftPress
+        ));
+        // too late
+        assert!(matches!(
+            g(&mut tracker, b"\x1b[<0;6;5M", t + ms(1000)),
+            Gesture::LeftPress
+        ));
+        // too far
+        assert!(matches!(
+            g(&mut tracker, b"\x1b[<0;9;5M", t + ms(1100)),
+            Gesture::LeftPress
+        ));
+        // another button
+        assert!(matches!(
+            g(&mut tracker, b"\x1b[<2;9;5M", t + ms(1200)),
+            Gesture::RightPress
+        ));
+    }
+
+    #[test]
+    fn drag() {
+        let mut tracker = GestureTracker::new();
+        let t = SystemTime::now();
+
+        tracker.track(decode_mi(b"\x1b[<0;2;3M").unwrap().remove(0), t);
+        tracker.track(decode_mi(b"\x1b[<32;5;3M").unwrap().remove(0), t);
+        let me = tracker.track(decode_mi(b"\x1b[<32;8;4M").unwrap().remove(0), t);
+        assert!(matches!(
+            me.gesture,
+            Gesture::Drag {
+                button: Button::Left,
+                from: [1, 2],
+                to: [7, 3]
+            }
+        ));
+
+        // the drag broke the click chain
+        tracker.track(decode_mi(b"\x1b[<0;8;4m").unwrap().remove(0), t);
+        let me = tracker.track(decode_mi(b"\x1b[<0;2;3M").unwrap().remove(0), t);
+        assert!(matches!(me.gesture, Gesture::LeftPress));
+    }
+}
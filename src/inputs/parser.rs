@@ -0,0 +1,763 @@
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::console::clipboard::decode_clipboard;
+
+use super::keyboard::grapheme::{cluster_len, open_ended};
+use super::keyboard::PasteEvent;
+use super::keyboard::{
+    decode_ki, decode_kitty, decode_paste, decode_vt, find, ALT, PASTE_END, PASTE_START,
+};
+use super::mouse::decode_mi;
+use super::window::decode_focus;
+use super::{InputEvent, Interaction};
+
+const ESC: u8 = 0x1b;
+
+// how many bytes of an unterminated OSC or DCS string or paste get buffered
+// the rest of a longer string is dropped up to its terminator,
+// a longer paste is reported in many PasteEvents up to its end sequence
+const STRING_CAP: usize = 1 << 20;
+
+/// incremental input decoder
+///
+/// takes the raw input bytes in chunks of any size, as they come out of the reads,
+/// and turns them into InputEvents.
+/// many events read at once all get decoded,
+/// and a sequence cut in 2 by the read stays buffered until its remaining bytes arrive
+///
+/// a lone ESC byte can either be the escape key or the start of an alt + key combination;
+/// it is kept waiting for the ESC timeout, after which flush resolves it to the escape key
+///
+/// OSC and DCS strings are terminal replies, e.g., the clipboard contents, which can be big
+/// and arrive in chunks further apart than the ESC timeout, and so can bracketed pastes, e.g., over ssh;
+/// once started they wait for their terminator instead, never turning into keys
+///
+/// # Examples
+/// ```
+/// let mut parser = InputParser::new().esc_timeout(Duration::from_millis(25));
+///
+/// loop {
+///     let timeout = parser.timeout().unwrap_or(Duration::from_secs(1));
+///     if let Some(bytes) = read_timeout(&mut reader, &mut buf, timeout) {
+///         for ie in parser.feed(bytes) {
+///             // handle the event
+///         }
+///     }
+///     for ie in parser.flush() {
+///         // handle the escape key
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct InputParser {
+    /// the bytes of the last incomplete sequence
+    buffer: Vec<u8>,
+    /// how long an incomplete sequence waits for the rest of its bytes
+    esc_timeout: Duration,
+    /// when the buffered bytes were last added to
+    since: Option<Instant>,
+    /// the sequence too big to be buffered whole whose remaining bytes are being read
+    overflow: Option<Overflow>,
+}
+
+// the rest of a sequence too big to be buffered whole
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Overflow {
+    /// an OSC or DCS string, from its introducer b']' or b'P', dropped up to its terminator
+    String(u8),
+    /// a bracketed paste, reported in parts up to its end sequence
+    Paste,
+}
+
+impl Default for InputParser {
+    fn default() -> Self {
+        Self {
+            buffer: vec![],
+            esc_timeout: Duration::from_millis(50),
+            since: None,
+            overflow: None,
+        }
+    }
+}
+
+// what sits at the start of the bytes
+#[derive(Debug, PartialEq)]
+enum Token {
+    /// a full sequence of the given length
+    Complete(usize),
+    /// the start of a sequence, more bytes are needed
+    Incomplete,
+    /// a byte that starts no sequence
+    Invalid,
+}
+
+impl InputParser {
+    /// creates a new InputParser with a 50 milliseconds ESC timeout
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// sets how long an incomplete sequence, a lone ESC most of the time,
+    /// waits for the rest of its bytes before flush resolves it
+    pub fn esc_timeout(mut self, esc_timeout: Duration) -> Self {
+        self.esc_timeout = esc_timeout;
+        self
+    }
+
+    /// decodes as many events as possible from the given bytes and the previously buffered ones
+    /// the trailing bytes of an incomplete sequence are kept for the next call
+    pub fn feed(&mut self, bytes: &[u8]) -> Vec<InputEvent> {
+        if bytes.is_empty() {
+            return vec![];
+        }
+
+        self.buffer.extend_from_slice(bytes);
+        self.since = Some(Instant::now());
+
+        self.parse(false)
+    }
+
+    /// resolves the buffered incomplete sequence if the ESC timeout has expired
+    /// should be called whenever a read times out or returns
+    ///
+    /// an unterminated OSC or DCS string or paste is left buffered
+    pub fn flush(&mut self) -> Vec<InputEvent> {
+        if self.waits_for_terminator() {
+            return vec![];
+        }
+
+        match self.since {
+            Some(since) if since.elapsed() >= self.esc_timeout => self.finish(),
+            _ => vec![],
+        }
+    }
+
+    /// resolves the buffered incomplete sequence right away
+    /// e.g., once stdin has been closed and no more bytes are coming
+    pub fn finish(&mut self) -> Vec<InputEvent> {
+        self.parse(true)
+    }
+
+    /// returns how long is left until flush resolves the buffered bytes
+    /// or None if nothing is buffered, or if it is an unterminated OSC or DCS string or paste
+    pub fn timeout(&self) -> Option<Duration> {
+        if self.waits_for_terminator() {
+            return None;
+        }
+
+        self.since
+            .map(|since| self.esc_timeout.saturating_sub(since.elapsed()))
+    }
+
+    // whether the buffered bytes are part of a terminal reply or a paste, which no timeout resolves
+    fn waits_for_terminator(&self) -> bool {
+        self.overflow.is_some() || open_string(&self.buffer) || self.buffer.starts_with(PASTE_START)
+    }
+
+    // the length of the buffered rest of an oversized sequence that has been handled,
+    // up to and including its terminator
+    // the paste text in there is reported, the string bytes are dropped
+    fn overflowed(&mut self, events: &mut Vec<InputEvent>) -> usize {
+        let (len, end) = match self.overflow {
+            None => return 0,
+            Some(Overflow::String(kind)) => {
+                let st = find(&self.buffer, b"\x1b\\").map(|idx| idx + 2);
+                let bel = match kind {
+                    b']' => self
+                        .buffer
+                        .iter()
+                        .position(|b| *b == 0x07)
+                        .map(|idx| idx + 1),
+                    _ => None,
+                };
+
+                match st.into_iter().chain(bel).min() {
+                    Some(len) => (len, true),
+                    // a trailing ESC is kept, the \ of the terminator may be in the next read
+                    None => (
+                        self.buffer.len() - (self.buffer.last() == Some(&ESC)) as usize,
+                        false,
+                    ),
+                }
+            }
+            Some(Overflow::Paste) => {
+                let (text, end) = match find(&self.buffer, PASTE_END) {
+                    Some(idx) => (idx, true),
+                    None => (paste_cut(&self.buffer), false),
+                };
+                if text > 0 {
+                    events.push(InputEvent {
+                        event: Interaction::PasteEvent(PasteEvent(
+                            String::from_utf8_lossy(&self.buffer[..text]).into_owned(),
+                        )),
+                        time: SystemTime::now(),
+                    });
+                }
+
+                (text + if end { PASTE_END.len() } else { 0 }, end)
+            }
+        };
+
+        if end {
+            self.overflow = None;
+        }
+
+        len
+    }
+
+    // decodes the buffer from the start, leaving an incomplete sequence at the end in there
+    // when forced, that sequence gets decoded as well
+    fn parse(&mut self, force: bool) -> Vec<InputEvent> {
+        let mut events = vec![];
+        let mut start = self.overflowed(&mut events);
+
+        while self.overflow.is_none() && start < self.buffer.len() {
+            let bytes = &self.buffer[start..];
+            let len = match token(bytes) {
+                // printable chars get grouped into grapheme clusters
+                Token::Complete(_) if bytes[0] >= 0x20 && bytes[0] != 0x7f => {
+                    match cluster(bytes) {
+                        Some(len) => len,
+                        None if !force => break,
+                        None => cluster_len(valid_utf8(bytes)),
+                    }
+                }
+                Token::Complete(len) => len,
+                Token::Invalid => 1,
+                // too big to buffer, reported as is while its remaining bytes get dropped
+                Token::Incomplete if open_string(bytes) && bytes.len() > STRING_CAP => {
+                    self.overflow = Some(Overflow::String(bytes[1]));
+                    bytes.len()
+                }
+                // too big to buffer, the paste so far is reported and the rest follows in parts
+                Token::Incomplete if bytes.starts_with(PASTE_START) && bytes.len() > STRING_CAP => {
+                    self.overflow = Some(Overflow::Paste);
+                    PASTE_START.len() + paste_cut(&bytes[PASTE_START.len()..])
+                }
+                Token::Incomplete if !force => break,
+                Token::Incomplete => forced(bytes),
+            };
+
+            events.push(InputEvent {
+                event: decode(&bytes[..len]),
+                time: SystemTime::now(),
+            });
+            start += len;
+        }
+
+        self.buffer.drain(..start);
+        if self.buffer.is_empty() {
+            self.since = None;
+        }
+
+        events
+    }
+}
+
+// the length of the grapheme cluster at the start of the bytes
+// or None if the cluster may go on in bytes that have not been read yet
+fn cluster(bytes: &[u8]) -> Option<usize> {
+    let valid = valid_utf8(bytes);
+    let len = cluster_len(valid);
+
+    // a char cut by the read follows the cluster, it could be a combining mark
+    let cut = valid.len() < bytes.len() && char_len(&bytes[valid.len()..]) == Token::Incomplete;
+    if len == valid.len() && (open_ended(valid) || cut) {
+        return None;
+    }
+
+    Some(len)
+}
+
+// the longest valid utf8 start of the bytes
+fn valid_utf8(bytes: &[u8]) -> &str {
+    match std::str::from_utf8(bytes) {
+        Ok(s) => s,
+        // can't fail, the bytes are valid up to there
+        Err(e) => std::str::from_utf8(&bytes[..e.valid_up_to()]).unwrap_or_default(),
+    }
+}
+
+// the length of an incomplete sequence that has to be decoded anyway
+// an open paste or OSC or DCS string is taken whole, anything else after an ESC is taken as alt + key
+fn forced(bytes: &[u8]) -> usize {
+    if bytes.starts_with(PASTE_START) || open_string(bytes) || bytes[0] != ESC || bytes.len() == 1 {
+        return bytes.len();
+    }
+
+    match char_len(&bytes[1..]) {
+        Token::Complete(len) => len + 1,
+        // a lone ESC followed by some broken char
+        _ => 1,
+    }
+}
+
+// how much of some unterminated paste text can be reported
+// without cutting a char or the start of the paste end sequence
+fn paste_cut(text: &[u8]) -> usize {
+    let end = (1..PASTE_END.len())
+        .rev()
+        .find(|len| text.ends_with(&PASTE_END[..*len]))
+        .unwrap_or(0);
+    let cut = text.len() - end;
+
+    // the first byte of the last char, which the read may have cut
+    let mut lead = cut;
+    while lead > 0 && cut - lead < 3 && text[lead - 1] & 0xc0 == 0x80 {
+        lead -= 1;
+    }
+    let len = match lead.checked_sub(1).map(|idx| text[idx]) {
+        Some(0xc0..=0xdf) => 2,
+        Some(0xe0..=0xef) => 3,
+        Some(0xf0..=0xff) => 4,
+        _ => return cut,
+    };
+
+    match cut - (lead - 1) < len {
+        true => lead - 1,
+        false => cut,
+    }
+}
+
+// an OSC or DCS whose body has started, rather than a lone alt + ] or alt + P
+fn open_string(bytes: &[u8]) -> bool {
+    bytes.len() > 2 && bytes[0] == ESC && matches!(bytes[1], b']' | b'P')
+}
+
+fn token(bytes: &[u8]) -> Token {
+    match bytes[0] {
+        ESC => escape(bytes),
+        _ => char_len(bytes),
+    }
+}
+
+// a single utf8 char, ascii and control chars included
+fn char_len(bytes: &[u8]) -> Token {
+    let len = match bytes[0] {
+        0x00..=0x7f => 1,
+        0xc2..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf4 => 4,
+        _ => return Token::Invalid,
+    };
+
+    if bytes.len() < len {
+        if bytes[1..].iter().all(|b| b & 0xc0 == 0x80) {
+            Token::Incomplete
+        } else {
+            Token::Invalid
+        }
+    } else if std::str::from_utf8(&bytes[..len]).is_ok() {
+        Token::Complete(len)
+    } else {
+        Token::Invalid
+    }
+}
+
+fn escape(bytes: &[u8]) -> Token {
+    match bytes.get(1) {
+        None => Token::Incomplete,
+        Some(b'[') => csi(bytes),
+        // SS3; ESC O final
+        Some(b'O') => match bytes.get(2) {
+            None => Token::Incomplete,
+            Some(0x40..=0x7e) => Token::Complete(3),
+            Some(_) => Token::Complete(2),
+        },
+        // OSC and DCS are terminated by a string terminator
+        Some(b']') | Some(b'P') => string(bytes),
+        // 3 escape bytes in a row are a lone Esc followed by an alt + Esc
+        Some(&ESC) => Token::Complete(1),
+        // alt + key
+        Some(_) => match char_len(&bytes[1..]) {
+            Token::Complete(len) => Token::Complete(len + 1),
+            Token::Incomplete => Token::Incomplete,
+            Token::Invalid => Token::Complete(1),
+        },
+    }
+}
+
+// ESC [ parameter bytes, intermediate bytes, final byte
+fn csi(bytes: &[u8]) -> Token {
+    if bytes.starts_with(PASTE_START) {
+        return match find(&bytes[PASTE_START.len()..], PASTE_END) {
+            Some(end) => Token::Complete(PASTE_START.len() + end + PASTE_END.len()),
+            None => Token::Incomplete,
+        };
+    }
+
+    // legacy mouse; ESC [ M followed by 3 raw bytes
+    if bytes.get(2) == Some(&b'M') {
+        return if bytes.len() >= 6 {
+            Token::Complete(6)
+        } else {
+            Token::Incomplete
+        };
+    }
+
+    // linux console F1 to F5; ESC [ [ A to ESC [ [ E
+    if bytes.get(2) == Some(&b'[') {
+        return match bytes.get(3) {
+            None => Token::Incomplete,
+            Some(b'A'..=b'E') => Token::Complete(4),
+            Some(_) => Token::Complete(3),
+        };
+    }
+
+    for (idx, b) in bytes.iter().enumerate().skip(2) {
+        match b {
+            // rxvt ends its shift + key sequences with a $, e.g., ESC [ 3 $
+            b'$' if idx > 2 && bytes[2..idx].iter().all(u8::is_ascii_digit) => {
+                return Token::Complete(idx + 1)
+            }
+            0x20..=0x3f => continue,
+            0x40..=0x7e => return Token::Complete(idx + 1),
+            // not a csi after all, alt + [
+            _ => return Token::Complete(2),
+        }
+    }
+
+    Token::Incomplete
+}
+
+// ESC ] or ESC P, up to BEL (OSC only) or ESC \
+fn string(bytes: &[u8]) -> Token {
+    for idx in 2..bytes.len() {
+        match bytes[idx] {
+            0x07 if bytes[1] == b']' => return Token::Complete(idx + 1),
+            ESC => match bytes.get(idx + 1) {
+                Some(b'\\') => return Token::Complete(idx + 2),
+                None => return Token::Incomplete,
+                // not terminated, alt + ] or alt + P
+                Some(_) => return Token::Complete(2),
+            },
+            _ => continue,
+        }
+    }
+
+    Token::Incomplete
+}
+
+// decodes one complete sequence
+// focus reports are window events, anything else that is not a mouse event or a paste is a key
+// OSC and DCS sequences are terminal replies, not user input, they are reported as unknown
+// but for the clipboard contents
+fn decode(bytes: &[u8]) -> Interaction {
+    let unknown = |_| Interaction::Unknown(bytes.to_vec());
+
+    if let Some(paste) = decode_paste(bytes) {
+        return Interaction::PasteEvent(paste);
+    } else if bytes.starts_with(b"\x1b[M") || bytes.starts_with(b"\x1b[<") {
+        return decode_mi(bytes).map_or_else(unknown, |mut v| Interaction::MouseEvent(v.remove(0)));
+    } else if let Some(ce) = decode_clipboard(bytes) {
+        return Interaction::ClipboardEvent(ce);
+    } else if open_string(bytes) {
+        return Interaction::Unknown(bytes.to_vec());
+    }
+
+    if let Some(we) = decode_focus(bytes) {
+        return Interaction::WindowEvent(we);
+    }
+
+    if let Some(ke) = decode_kitty(bytes) {
+        return Interaction::KbdEvent(ke);
+    }
+
+    // SS3 keys, e.g., the keypad in application keypad mode, and the rxvt and linux console keys
+    if let Some(ke) = decode_vt(bytes) {
+        return Interaction::KbdEvent(ke);
+    }
+
+    let ke = match bytes {
+        // alt + key
+        [ESC, b, ..] if (*b != b'[' && *b != b'O') || bytes.len() == 2 => decode_ki(&bytes[1..])
+            .map(|mut ke| {
+                ke.modifiers.0 |= ALT;
+                ke
+            }),
+        _ => decode_ki(bytes),
+    };
+
+    ke.map_or_else(unknown, Interaction::KbdEvent)
+}
+
+#[cfg(test)]
+mod test_parser {
+    use super::{InputParser, STRING_CAP};
+    use crate::inputs::keyboard::{Char, Modifiers, CC};
+    use crate::inputs::mouse::Gesture;
+    use crate::inputs::window::WindowEvent;
+    use crate::inputs::Interaction;
+    use std::time::Duration;
+
+    fn chars(events: &[crate::inputs::InputEvent]) -> Vec<&Char> {
+        events
+            .iter()
+            .map(|ie| match &ie.event {
+                Interaction::KbdEvent(ke) => &ke.char,
+                e => panic!("not a keyboard event: {:?}", e),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn batched() {
+        let mut parser = InputParser::new();
+        let events = parser.feed("ab\x1b[Aé\x1b[1;5C\r".as_bytes());
+        assert_eq!(
+            chars(&events),
+            [
+                &Char::Char('a'),
+                &Char::Char('b'),
+                &Char::CC(CC::Up),
+                &Char::Char('é'),
+                &Char::CC(CC::Right),
+                &Char::CC(CC::CR),
+            ]
+        );
+
+        let events = parser.feed(b"\x1b[<0;3;4M\x1b[200~x\x1b[201~q");
+        assert_eq!(events.len(), 3);
+        assert!(
+            matches!(&events[0].event, Interaction::MouseEvent(me) if matches!(me.gesture, Gesture::LeftPress))
+        );
+        assert!(matches!(&events[1].event, Interaction::PasteEvent(p) if p.0 == "x"));
+        assert!(parser.timeout().is_none());
+    }
+
+    #[test]
+    fn split() {
+        let mut parser = InputParser::new();
+        assert!(parser.feed(b"\x1b[1;").is_empty());
+        assert!(parser.feed(b"5").is_empty());
+        let events = parser.feed(b"Dz");
+        assert_eq!(chars(&events), [&Char::CC(CC::Left), &Char::Char('z')]);
+
+        // a multi byte char cut in half
+        let bytes = "ل".as_bytes();
+        assert!(parser.feed(&bytes[..1]).is_empty());
+        assert_eq!(chars(&parser.feed(&bytes[1..])), [&Char::Char('ل')]);
+
+        // a paste spread over many reads
+        assert!(parser.feed(b"\x1b[200~some ").is_empty());
+        assert!(parser.feed(b"text\x1b[2").is_empty());
+        let events = parser.feed(b"01~");
+        assert!(matches!(&events[0].event, Interaction::PasteEvent(p) if p.0 == "some text"));
+    }
+
+    #[test]
+    fn esc_timeout() {
+        let mut parser = InputParser::new().esc_timeout(Duration::ZERO);
+
+        // alt + x
+        let events = parser.feed(b"\x1bx");
+        assert_eq!(chars(&events), [&Char::Char('x')]);
+        match &events[0].event {
+            Interaction::KbdEvent(ke) => assert_eq!(ke.modifiers, Modifiers(4)),
+            _ => unreachable!(),
+        }
+
+        // a lone ESC waits for the timeout
+        assert!(parser.feed(b"\x1b").is_empty());
+        assert!(parser.timeout().is_some());
+        assert_eq!(chars(&parser.flush()), [&Char::CC(CC::ESC)]);
+        assert!(parser.timeout().is_none());
+
+        // ESC [ with nothing after it is alt + [
+        let mut parser = InputParser::new().esc_timeout(Duration::from_secs(60));
+        assert!(parser.feed(b"\x1b[").is_empty());
+        assert!(parser.flush().is_empty());
+        let events = parser.finish();
+        assert_eq!(chars(&events), [&Char::Char('[')]);
+    }
+
+    #[test]
+    fn graphemes() {
+        let mut parser = InputParser::new();
+        let events = parser.feed("لاe\u{301}👍🏽x".as_bytes());
+        assert_eq!(
+            chars(&events),
+            [
+                &Char::Grapheme("لا".to_string()),
+                &Char::Grapheme("e\u{301}".to_string()),
+                &Char::Grapheme("👍🏽".to_string()),
+                &Char::Char('x'),
+            ]
+        );
+
+        // a zwj sequence cut by the read
+        assert!(parser.feed("👨\u{200d}".as_bytes()).is_empty());
+        let events = parser.feed("👩".as_bytes());
+        assert_eq!(
+            chars(&events),
+            [&Char::Grapheme("👨\u{200d}👩".to_string())]
+        );
+
+        // a combining mark cut in half by the read
+        let bytes = "a\u{301}".as_bytes();
+        assert!(parser.feed(&bytes[..2]).is_empty());
+        let events = parser.feed(&bytes[2..]);
+        assert_eq!(chars(&events), [&Char::Grapheme("a\u{301}".to_string())]);
+
+        // the flush gives up on the rest of the cluster
+        let mut parser = InputParser::new().esc_timeout(Duration::ZERO);
+        assert!(parser.feed("🇯".as_bytes()).is_empty());
+        assert_eq!(chars(&parser.flush()), [&Char::Char('🇯')]);
+    }
+
+    #[test]
+    fn focus() {
+        let mut parser = InputParser::new();
+        let events = parser.feed(b"\x1b[Ox\x1b[I");
+        assert!(matches!(
+            &events[0].event,
+            Interaction::WindowEvent(WindowEvent::WindowLostFocus)
+        ));
+        assert!(matches!(&events[1].event, Interaction::KbdEvent(_)));
+        assert!(matches!(
+            &events[2].event,
+            Interaction::WindowEvent(WindowEvent::WindowGainedFocus)
+        ));
+    }
+
+    #[test]
+    fn clipboard() {
+        let mut parser = InputParser::new();
+        assert!(parser.feed(b"\x1b]52;c;aGVs").is_empty());
+        let events = parser.feed(b"bG8=\x1b\\a");
+        assert!(matches!(
+            &events[0].event,
+            Interaction::ClipboardEvent(ce) if ce.text == "hello"
+        ));
+        assert!(matches!(&events[1].event, Interaction::KbdEvent(_)));
+
+        // a big reply split over reads further apart than the ESC timeout
+        let mut parser = InputParser::new().esc_timeout(Duration::ZERO);
+        let text = "x".repeat(3000);
+        let reply = format!("\x1b]52;c;{}\x07", "eHh4".repeat(1000));
+        let (head, tail) = reply.as_bytes().split_at(1000);
+        assert!(parser.feed(head).is_empty());
+        assert!(parser.timeout().is_none());
+        assert!(parser.flush().is_empty());
+        let events = parser.feed(tail);
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            &events[0].event,
+            Interaction::ClipboardEvent(ce) if ce.text == text
+        ));
+
+        // ESC ] alone is still alt + ]
+        assert!(parser.feed(b"\x1b]").is_empty());
+        assert_eq!(chars(&parser.flush()), [&Char::Char(']')]);
+
+        // a closed stdin reports the unterminated reply as is
+        assert!(parser.feed(b"\x1bP1$r0m").is_empty());
+        let events = parser.finish();
+        assert!(matches!(&events[0].event, Interaction::Unknown(b) if b == b"\x1bP1$r0m"));
+    }
+
+    #[test]
+    fn slow_paste() {
+        // the rest of the paste comes later than the ESC timeout
+        let mut parser = InputParser::new().esc_timeout(Duration::ZERO);
+        assert!(parser.feed(b"\x1b[200~line 1\n").is_empty());
+        assert!(parser.timeout().is_none());
+        assert!(parser.flush().is_empty());
+        assert!(parser.feed(b"\tline 2\x1b[20").is_empty());
+        assert!(parser.flush().is_empty());
+        let events = parser.feed(b"1~a");
+        assert!(
+            matches!(&events[0].event, Interaction::PasteEvent(p) if p.0 == "line 1\n\tline 2")
+        );
+        assert_eq!(chars(&events[1..]), [&Char::Char('a')]);
+    }
+
+    #[test]
+    fn oversized_paste() {
+        let mut parser = InputParser::new().esc_timeout(Duration::ZERO);
+        let text = "é".repeat(STRING_CAP);
+        let mut bytes = b"\x1b[200~".to_vec();
+        bytes.extend_from_slice(text.as_bytes());
+        bytes.extend_from_slice(b"\x1b[201~x");
+
+        // cut in the middle of a char, then in the middle of the end sequence
+        let mut pasted = String::new();
+        let mut events = vec![];
+        for chunk in [
+            &bytes[..STRING_CAP + 3],
+            &bytes[STRING_CAP + 3..bytes.len() - 4],
+            &bytes[bytes.len() - 4..],
+        ] {
+            events.append(&mut parser.feed(chunk));
+            events.append(&mut parser.flush());
+        }
+        for ie in &events[..events.len() - 1] {
+            match &ie.event {
+                Interaction::PasteEvent(p) => pasted.push_str(&p.0),
+                e => panic!("not a paste: {:?}", e),
+            }
+        }
+        assert_eq!(pasted, text);
+        assert_eq!(chars(&events[events.len() - 1..]), [&Char::Char('x')]);
+        assert!(parser.timeout().is_none());
+    }
+
+    #[test]
+    fn oversized_string() {
+        let mut parser = InputParser::new().esc_timeout(Duration::ZERO);
+        let mut reply = b"\x1b]52;c;".to_vec();
+        reply.resize(STRING_CAP + 1, b'A');
+
+        let events = parser.feed(&reply);
+        assert!(matches!(&events[0].event, Interaction::Unknown(b) if b.len() == STRING_CAP + 1));
+
+        // the rest of the reply is dropped, the ST split in half included
+        assert!(parser.feed(b"AAAA\x1b").is_empty());
+        assert!(parser.flush().is_empty());
+        let events = parser.feed(b"\\a");
+        assert_eq!(chars(&events), [&Char::Char('a')]);
+        assert!(parser.timeout().is_none());
+    }
+
+    #[test]
+    fn unknown() {
+        let mut parser = InputParser::new();
+        let events = parser.feed(b"\x1b[1;99Xa\xff\x1b]11;rgb:0/0/0\x07");
+        assert!(matches!(&events[0].event, Interaction::Unknown(b) if b == b"\x1b[1;99X"));
+        assert!(matches!(&events[1].event, Interaction::KbdEvent(_)));
+        assert!(matches!(&events[2].event, Interaction::Unknown(b) if b == b"\xff"));
+        assert!(matches!(&events[3].event, Interaction::Unknown(b) if b.starts_with(b"\x1b]11;")));
+    }
+
+    #[test]
+    fn no_panic() {
+        const ALPHABET: &[u8] = b"\x1b\x1b\x1b[[[O]P\\;;:~~$^@uM<0123456789ABCDFHIPQRSZam\x07\x00\x7f\x80\xbf\xc3\xd9\xe2\xf0\xff";
+
+        // xorshift, to get the same inputs on every run
+        let mut seed: u64 = 0x9e3779b97f4a7c15;
+        let mut parser = InputParser::new();
+        for _ in 0..100_000 {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            let len = (seed % 9) as usize;
+            let bytes = (0..len)
+                .map(|i| {
+                    let r = (seed >> (i * 7)) as usize;
+                    if r % 11 == 0 {
+                        r as u8
+                    } else {
+                        ALPHABET[r % ALPHABET.len()]
+                    }
+                })
+                .collect::<Vec<u8>>();
+
+            _ = parser.feed(&bytes);
+            if seed % 5 == 0 {
+                _ = parser.finish();
+            }
+        }
+        _ = parser.finish();
+        assert!(parser.timeout().is_none());
+    }
+}
@@ -0,0 +1,225 @@
+use std::any::Any;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime};
+
+use super::{read_timeout, woken, CustomEvent, InputEvent, InputParser, InputSource, Interaction};
+use crate::console::winsize::winsize;
+
+// how long the reader thread waits on stdin before checking whether it should stop
+const STOP_CHECK: Duration = Duration::from_millis(50);
+
+/// reads and decodes input in a background thread
+/// then sends the resulting InputEvents through a channel
+///
+/// the thread holds the stdin lock for as long as the EventReader lives,
+/// so stdin should not be locked anywhere else in the meantime
+///
+/// the thread stops once the input is closed;
+/// the events sent before that can still be received, then recv returns None
+/// and the EventSenders stop sending
+///
+/// # Examples
+/// ```
+/// let events = EventReader::spawn();
+///
+/// let sender = events.sender();
+/// std::thread::spawn(move || {
+///     let body = fetch();
+///     sender.send(body);
+/// });
+///
+/// loop {
+///     frames(60);
+///     while let Some(ie) = events.try_recv() {
+///         match ie.event {
+///             Interaction::Custom(ce) => { /* network result */ }
+///             _ => { /* keyboard, mouse, paste or window events */ }
+///         }
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct EventReader {
+    rx: Receiver<InputEvent>,
+    // the only sender of the channel, shared with the EventSenders
+    // the thread drops it when it stops, which disconnects the channel
+    tx: Arc<Mutex<Option<Sender<InputEvent>>>>,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl EventReader {
+    /// spawns the input reading thread and returns the receiving end of its events
+    pub fn spawn() -> Self {
+        Self::start(|| std::io::stdin().lock())
+    }
+
+    /// spawns the input reading thread on another InputSource than stdin, e.g., a Replay
+    ///
+    /// # Examples
+    /// ```
+    /// let file = std::fs::File::open("bug.rec").unwrap();
+    /// let events = EventReader::from_source(Replay::new(file).unwrap());
+    /// ```
+    pub fn from_source<S: InputSource + Send + 'static>(source: S) -> Self {
+        Self::start(move || source)
+    }
+
+    // the source is opened in the thread, the stdin lock can't be moved into it
+    fn start<S: InputSource>(open: impl FnOnce() -> S + Send + 'static) -> Self {
+        let (tx, rx) = channel();
+        let tx = Arc::new(Mutex::new(Some(tx)));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let handle = {
+            let tx = tx.clone();
+            let stop = stop.clone();
+
+            std::thread::spawn(move || {
+                let mut reader = open();
+                let mut ws = winsize::from_ioctl();
+                let mut buf = vec![];
+                let mut parser = InputParser::new();
+
+                while !stop.load(Ordering::Relaxed) {
+                    // wake up in time to resolve a pending lone ESC
+                    let timeout = parser.timeout().map_or(STOP_CHECK, |t| t.min(STOP_CHECK));
+
+                    let mut events = match read_timeout(&mut reader, &mut buf, timeout) {
+                        Some(bytes) if bytes.is_empty() => woken(&mut ws).into_iter().collect(),
+                        Some(bytes) => parser.feed(bytes),
+                        None => vec![],
+                    };
+                    events.append(&mut parser.flush());
+
+                    // a closed stdin would wake the reads up forever
+                    let closed = events
+                        .iter()
+                        .any(|ie| matches!(ie.event, Interaction::Closed));
+                    let sent = match tx.lock().unwrap().as_ref() {
+                        Some(tx) => events.into_iter().all(|ie| tx.send(ie).is_ok()),
+                        None => false,
+                    };
+                    if !sent || closed {
+                        break;
+                    }
+                }
+
+                // recv gets None once the events left in the channel are received
+                tx.lock().unwrap().take();
+            })
+        };
+
+        Self {
+            rx,
+            tx,
+            stop,
+            handle: Some(handle),
+        }
+    }
+
+    /// returns a new sender that can inject custom events into this reader's channel
+    pub fn sender(&self) -> EventSender {
+        EventSender(self.tx.clone())
+    }
+
+    /// blocks until the next event arrives
+    /// returns None once the thread has stopped and all its events have been received
+    pub fn recv(&self) -> Option<InputEvent> {
+        self.rx.recv().ok()
+    }
+
+    /// returns the next event if there is one, without blocking
+    pub fn try_recv(&self) -> Option<InputEvent> {
+        self.rx.try_recv().ok()
+    }
+
+    /// waits for the next event for at most the given timeout
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<InputEvent> {
+        self.rx.recv_timeout(timeout).ok()
+    }
+}
+
+impl Drop for EventReader {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            _ = handle.join();
+        }
+    }
+}
+
+/// injects user defined events into an EventReader's channel
+/// can be cloned and moved to other threads
+#[derive(Debug, Clone)]
+pub struct EventSender(Arc<Mutex<Option<Sender<InputEvent>>>>);
+
+impl EventSender {
+    /// sends the value as an Interaction::Custom event
+    /// returns false if the EventReader has been dropped or its thread has stopped
+    pub fn send<T: Any + Send>(&self, value: T) -> bool {
+        let Some(tx) = self.0.lock().unwrap().clone() else {
+            return false;
+        };
+
+        tx.send(InputEvent {
+            event: Interaction::Custom(CustomEvent(Box::new(value))),
+            time: SystemTime::now(),
+        })
+        .is_ok()
+    }
+}
+
+#[cfg(test)]
+mod test_reader {
+    use super::EventReader;
+    use crate::inputs::keyboard::Char;
+    use crate::inputs::{Interaction, Replay};
+
+    #[test]
+    fn replayed() {
+        let events = EventReader::from_source(Replay::new("0 ab\n".as_bytes()).unwrap());
+
+        let mut chars = vec![];
+        let mut closed = false;
+        while let Some(ie) = events.recv() {
+            match ie.event {
+                Interaction::KbdEvent(ke) => chars.push(ke.char),
+                Interaction::Closed => closed = true,
+                _ => (),
+            }
+        }
+        // the thread stopped on the end of the replay and recv didn't block forever
+        assert_eq!(chars, [Char::Char('a'), Char::Char('b')]);
+        assert!(closed);
+        assert!(!events.sender().send(0));
+    }
+
+    #[test]
+    fn custom() {
+        // a real time replay keeps the thread waiting for a while
+        let replay = Replay::new("60000 a\n".as_bytes()).unwrap().real_time(true);
+        let events = EventReader::from_source(replay);
+
+        let sender = events.sender();
+        std::thread::spawn(move || assert!(sender.send(42u8)))
+            .join()
+            .unwrap();
+
+        let ie = events.recv().unwrap();
+        let Interaction::Custom(ce) = ie.event else {
+            panic!("expected a custom event, got {:?}", ie.event);
+        };
+        assert_eq!(ce.downcast_ref::<u8>(), Some(&42));
+
+        // the drop stops and joins the thread without waiting for the replay
+        let start = std::time::Instant::now();
+        let sender = events.sender();
+        drop(events);
+        assert!(start.elapsed() < std::time::Duration::from_secs(1));
+        assert!(!sender.send(0));
+    }
+}
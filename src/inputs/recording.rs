@@ -0,0 +1,495 @@
+use std::collections::VecDeque;
+use std::io::{Error, ErrorKind, Read, Write};
+use std::time::{Duration, Instant};
+
+use super::window::WindowEvent;
+use super::{InputSource, Ready, REPLAYED};
+use crate::console::signals::{self, SIGCONT, SIGTSTP, SIGWINCH};
+use crate::console::winsize::winsize;
+
+// the recording file format, one read per line
+//
+// # comment
+// <milliseconds since the recording started> <bytes>
+// <milliseconds since the recording started> <signal>
+//
+// the bytes are printable ascii, with \\ for a backslash and \xNN for every other byte
+// a read that a signal woke up has the signal instead of the bytes:
+// R <cols> <rows> for a window resize, S for a suspension, C for a continue signal alone
+// and - for a signal with nothing to report
+const HEADER: &str = "# ragout input recording\n";
+
+/// wraps an InputSource and writes every read it gets into a recording
+/// the recording can be played back later with a Replay
+///
+/// every line gets flushed as soon as it is written,
+/// so the recording survives a crash of the recorded program
+///
+/// # Examples
+/// ```
+/// let file = std::fs::File::create("bug.rec").unwrap();
+/// let mut reader = Recorder::new(std::io::stdin().lock(), file);
+///
+/// loop {
+///     let ie = event(read(&mut reader, &mut buf), &mut ws);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Recorder<S: InputSource, W: Write> {
+    source: S,
+    out: W,
+    start: Instant,
+}
+
+impl<S: InputSource, W: Write> Recorder<S, W> {
+    /// starts recording the reads of the given source into out
+    pub fn new(source: S, mut out: W) -> Self {
+        _ = out.write_all(HEADER.as_bytes());
+        _ = out.flush();
+
+        Self {
+            source,
+            out,
+            start: Instant::now(),
+        }
+    }
+
+    /// stops recording and gives back the source and the recording writer
+    pub fn into_inner(self) -> (S, W) {
+        (self.source, self.out)
+    }
+
+    fn record(&mut self, bytes: &str) {
+        _ = writeln!(self.out, "{} {}", self.start.elapsed().as_millis(), bytes);
+        _ = self.out.flush();
+    }
+}
+
+impl<S: InputSource, W: Write> InputSource for Recorder<S, W> {
+    fn wait(&mut self, timeout: Option<Duration>) -> Option<Ready> {
+        self.source.wait(timeout)
+    }
+
+    fn fill(&mut self, buffer: &mut Vec<u8>) {
+        let start = buffer.len();
+        self.source.fill(buffer);
+
+        let line = escape(&buffer[start..]);
+        self.record(&line);
+    }
+
+    fn drain(&mut self) {
+        self.source.drain();
+        let line = Signal::pending().line();
+        self.record(&line);
+    }
+}
+
+// a signal read, as it gets recorded and replayed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Signal {
+    // a window resize, with the new cols and rows
+    Resized(u16, u16),
+    // a suspension, replayed as the resume that follows it
+    Suspended,
+    // a continue signal alone
+    Continued,
+    // a signal with nothing to report
+    Other,
+}
+
+impl Signal {
+    // the signal that the woken read is about to report, left for the read to take
+    // in the order inputs::woken takes them
+    fn pending() -> Self {
+        if let Some(signal) = REPLAYED.get() {
+            return signal;
+        }
+
+        if signals::pending(SIGTSTP) {
+            Self::Suspended
+        } else if signals::pending(SIGCONT) {
+            Self::Continued
+        } else if signals::pending(SIGWINCH) {
+            let ws = winsize::from_ioctl();
+            Self::Resized(ws.cols(), ws.rows())
+        } else {
+            Self::Other
+        }
+    }
+
+    // the event a replay of this signal reports
+    pub(crate) fn event(self) -> Option<WindowEvent> {
+        match self {
+            Self::Resized(cols, rows) => Some(WindowEvent::WindowResized { cols, rows }),
+            Self::Suspended | Self::Continued => Some(WindowEvent::Resumed),
+            Self::Other => None,
+        }
+    }
+
+    fn line(self) -> String {
+        match self {
+            Self::Resized(cols, rows) => format!("R {} {}", cols, rows),
+            Self::Suspended => "S".to_string(),
+            Self::Continued => "C".to_string(),
+            Self::Other => "-".to_string(),
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "S" => Some(Self::Suspended),
+            "C" => Some(Self::Continued),
+            "-" => Some(Self::Other),
+            s => {
+                let (cols, rows) = s.strip_prefix("R ")?.split_once(' ')?;
+                Some(Self::Resized(cols.parse().ok()?, rows.parse().ok()?))
+            }
+        }
+    }
+}
+
+// a recorded read, either its bytes or the signal that woke it up
+#[derive(Debug, Clone)]
+enum Recorded {
+    Bytes(Vec<u8>),
+    Signal(Signal),
+}
+
+/// plays a recording made by a Recorder back
+/// can be used wherever the stdin lock is used to read input
+///
+/// by default the reads come as fast as they are asked for;
+/// in real time, each one waits for the time it was recorded at
+///
+/// a recorded signal wakes the read up like the real one would,
+/// and inputs::event returns the recorded event, e.g., the WindowResized with the recorded size
+/// a replayed suspension doesn't stop the program, only its WindowEvent::Resumed is returned
+///
+/// once the recording is over, the replay acts like a closed stdin
+/// and every read gets no bytes at all
+///
+/// # Examples
+/// ```
+/// let file = std::fs::File::open("bug.rec").unwrap();
+/// let mut reader = Replay::new(file).unwrap().real_time(true);
+///
+/// while !reader.is_over() {
+///     let ie = event(read(&mut reader, &mut buf), &mut ws);
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Replay {
+    /// when each read happened and its bytes or signal
+    reads: VecDeque<(Duration, Recorded)>,
+    real_time: bool,
+    /// when the playback started
+    start: Option<Instant>,
+}
+
+impl Replay {
+    /// loads a recording
+    ///
+    /// # Errors
+    ///
+    /// returns an error if the recording can't be read or if one of its lines is malformed
+    pub fn new<R: Read>(mut recording: R) -> Result<Self, Error> {
+        let mut text = String::new();
+        recording.read_to_string(&mut text)?;
+
+        let mut reads = VecDeque::new();
+        for (idx, line) in text.lines().enumerate() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let read = parse_line(line).ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("malformed recording line {}: {}", idx + 1, line),
+                )
+            })?;
+            reads.push_back(read);
+        }
+
+        Ok(Self {
+            reads,
+            real_time: false,
+            start: None,
+        })
+    }
+
+    /// toggles real time playback, where each read waits for the time it was recorded at
+    pub fn real_time(mut self, real_time: bool) -> Self {
+        self.real_time = real_time;
+        self
+    }
+
+    /// returns whether all the recorded reads have been played
+    pub fn is_over(&self) -> bool {
+        self.reads.is_empty()
+    }
+
+    // how long until the next read is due
+    fn due(&mut self) -> Duration {
+        let start = *self.start.get_or_insert_with(Instant::now);
+
+        match self.reads.front() {
+            Some((at, _)) if self.real_time => at.saturating_sub(start.elapsed()),
+            _ => Duration::ZERO,
+        }
+    }
+}
+
+impl InputSource for Replay {
+    fn wait(&mut self, timeout: Option<Duration>) -> Option<Ready> {
+        let due = self.due();
+        if timeout.is_some_and(|timeout| timeout < due) {
+            std::thread::sleep(timeout.unwrap_or_default());
+            return None;
+        }
+        std::thread::sleep(due);
+
+        match self.reads.front() {
+            Some((_, Recorded::Signal(_))) => Some(Ready::Signal),
+            Some((_, Recorded::Bytes(_))) => Some(Ready::Input),
+            // like a closed stdin, always readable and never any bytes
+            None => Some(Ready::Input),
+        }
+    }
+
+    fn fill(&mut self, buffer: &mut Vec<u8>) {
+        if let Some((_, Recorded::Bytes(_))) = self.reads.front() {
+            if let Some((_, Recorded::Bytes(bytes))) = self.reads.pop_front() {
+                buffer.extend_from_slice(&bytes);
+            }
+        }
+    }
+
+    fn drain(&mut self) {
+        if let Some((_, Recorded::Signal(signal))) = self.reads.front() {
+            // inputs::event reports it in place of the real signals
+            REPLAYED.set(Some(*signal));
+            self.reads.pop_front();
+        }
+    }
+}
+
+fn parse_line(line: &str) -> Option<(Duration, Recorded)> {
+    let (ms, bytes) = line.split_once(' ')?;
+    let at = Duration::from_millis(ms.parse().ok()?);
+
+    match Signal::parse(bytes) {
+        Some(signal) => Some((at, Recorded::Signal(signal))),
+        // a malformed resize is not read as bytes either
+        None if bytes.starts_with("R ") => None,
+        None => Some((at, Recorded::Bytes(unescape(bytes)?))),
+    }
+}
+
+// printable ascii stays as is, everything else becomes \xNN
+fn escape(bytes: &[u8]) -> String {
+    // bytes that would read as a signal get their first byte escaped
+    let signal = matches!(bytes, b"-" | b"S" | b"C") || bytes.starts_with(b"R ");
+
+    let mut s = String::new();
+    for (idx, b) in bytes.iter().enumerate() {
+        match b {
+            b'\\' => s.push_str("\\\\"),
+            _ if signal && idx == 0 => s.push_str(&format!("\\x{:02x}", b)),
+            0x20..=0x7e => s.push(*b as char),
+            _ => s.push_str(&format!("\\x{:02x}", b)),
+        }
+    }
+
+    s
+}
+
+fn unescape(s: &str) -> Option<Vec<u8>> {
+    let mut bytes = vec![];
+    let mut chars = s.bytes();
+    while let Some(b) = chars.next() {
+        if b != b'\\' {
+            bytes.push(b);
+            continue;
+        }
+
+        match chars.next()? {
+            b'\\' => bytes.push(b'\\'),
+            b'x' => {
+                let hex = [chars.next()?, chars.next()?];
+                let hex = std::str::from_utf8(&hex).ok()?;
+                bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            }
+            _ => return None,
+        }
+    }
+
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod test_recording {
+    use super::{escape, unescape, Recorder, Replay};
+    use crate::console::winsize::winsize;
+    use crate::inputs::keyboard::{Char, CC};
+    use crate::inputs::window::WindowEvent;
+    use crate::inputs::{event, read, read_timeout, InputParser, Interaction};
+    use std::time::{Duration, Instant};
+
+    const RECORDING: &str = "# ragout input recording
+0 ab
+5 \\x1b[A
+7 R 120 40
+
+12 \\xd9\\x84\\x1b[1;
+20 5C\\\\
+";
+
+    #[test]
+    fn escaping() {
+        let bytes = "a \\-\x1b[<0;1;2M\r\nلا".as_bytes();
+        assert_eq!(unescape(&escape(bytes)).unwrap(), bytes);
+        assert_eq!(escape(b"\x1b[A\\"), "\\x1b[A\\\\");
+        assert_eq!(escape(b"-"), "\\x2d");
+        assert_eq!(escape(b"S"), "\\x53");
+        assert_eq!(escape(b"R 1 2"), "\\x52 1 2");
+        assert_eq!(escape(b"RS"), "RS");
+        assert!(unescape("\\x1").is_none());
+        assert!(unescape("\\n").is_none());
+    }
+
+    #[test]
+    fn replay() {
+        let mut reader = Replay::new(RECORDING.as_bytes()).unwrap();
+        let mut buf = vec![];
+        let mut parser = InputParser::new();
+
+        let mut events = vec![];
+        let mut signals = 0;
+        while !reader.is_over() {
+            let bytes = read(&mut reader, &mut buf);
+            if bytes.is_empty() {
+                signals += 1;
+                // the recorded resize, not whatever the real window is
+                let ie = event(bytes, &mut winsize::from_ioctl());
+                assert!(matches!(
+                    ie.event,
+                    Interaction::WindowEvent(WindowEvent::WindowResized {
+                        cols: 120,
+                        rows: 40
+                    })
+                ));
+                continue;
+            }
+            events.extend(parser.feed(bytes));
+        }
+        assert_eq!(signals, 1);
+
+        let chars = events
+            .iter()
+            .filter_map(|ie| match &ie.event {
+                Interaction::KbdEvent(ke) => Some(&ke.char),
+                _ => None,
+            })
+            .collect::<Vec<&Char>>();
+        assert_eq!(
+            chars,
+            [
+                &Char::Char('a'),
+                &Char::Char('b'),
+                &Char::CC(CC::Up),
+                &Char::Char('ل'),
+                &Char::CC(CC::Right),
+                &Char::Char('\\'),
+            ]
+        );
+
+        // a finished replay reads like a closed stdin
+        assert!(read(&mut reader, &mut buf).is_empty());
+        let mut ws = winsize::from_ioctl();
+        let ie = event(read(&mut reader, &mut buf), &mut ws);
+        assert!(matches!(ie.event, Interaction::Closed));
+
+        assert!(Replay::new("12".as_bytes()).is_err());
+        assert!(Replay::new("x ab".as_bytes()).is_err());
+        assert!(Replay::new("0 R 80".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn real_time() {
+        let mut reader = Replay::new("30 a\n".as_bytes()).unwrap().real_time(true);
+        let mut buf = vec![];
+
+        let start = Instant::now();
+        assert!(read_timeout(&mut reader, &mut buf, Duration::from_millis(5)).is_none());
+        assert_eq!(read(&mut reader, &mut buf), b"a");
+        assert!(start.elapsed() >= Duration::from_millis(30));
+    }
+
+    #[test]
+    fn record() {
+        let reader = Replay::new(RECORDING.as_bytes()).unwrap();
+        let mut recorder = Recorder::new(reader, vec![]);
+        let mut buf = vec![];
+        for _ in 0..5 {
+            read(&mut recorder, &mut buf);
+        }
+
+        // the timestamps differ, the reads don't
+        let (_, out) = recorder.into_inner();
+        let mut replay = Replay::new(out.as_slice()).unwrap();
+        let mut original = Replay::new(RECORDING.as_bytes()).unwrap();
+        for _ in 0..5 {
+            assert_eq!(
+                read(&mut replay, &mut buf).clone(),
+                read(&mut original, &mut vec![]).clone()
+            );
+        }
+        assert!(replay.is_over());
+    }
+
+    #[test]
+    fn signals() {
+        let reader = Replay::new("0 R 100 30\n1 S\n2 C\n3 -\n4 S\\x53\n".as_bytes()).unwrap();
+        let mut recorder = Recorder::new(reader, vec![]);
+        let mut buf = vec![];
+        let mut ws = winsize::from_ioctl();
+        for _ in 0..5 {
+            event(read(&mut recorder, &mut buf), &mut ws);
+        }
+
+        // the signals get recorded as the events they were read as
+        let (_, out) = recorder.into_inner();
+        let lines = String::from_utf8(out.clone()).unwrap();
+        let lines = lines
+            .lines()
+            .skip(1)
+            .map(|line| line.split_once(' ').unwrap().1)
+            .collect::<Vec<&str>>();
+        assert_eq!(lines, ["R 100 30", "S", "C", "-", "SS"]);
+
+        let mut replay = Replay::new(out.as_slice()).unwrap();
+        let mut events = vec![];
+        while !replay.is_over() {
+            events.push(event(read(&mut replay, &mut buf), &mut ws).event);
+        }
+        assert!(matches!(
+            events[0],
+            Interaction::WindowEvent(WindowEvent::WindowResized {
+                cols: 100,
+                rows: 30
+            })
+        ));
+        assert!(matches!(
+            events[1],
+            Interaction::WindowEvent(WindowEvent::Resumed)
+        ));
+        assert!(matches!(
+            events[2],
+            Interaction::WindowEvent(WindowEvent::Resumed)
+        ));
+        assert!(matches!(&events[3], Interaction::Unknown(b) if b.is_empty()));
+        assert!(matches!(&events[4], Interaction::KbdEvent(_)));
+    }
+}
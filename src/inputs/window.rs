@@ -1,56 +1,31 @@
-use crate::console::winsize::winsize;
+/// focus reporting, turns the terminal window gaining and losing the focus into input events
+pub mod focus;
+/// SIGWINCH listening, turns terminal window resizes into input events
+pub mod resize;
+/// SIGTSTP and SIGCONT listening, job control aware suspend and resume
+pub mod suspend;
 
-#[derive(Debug)]
+pub use focus::{decode_focus, disable_focus_reporting, enable_focus_reporting};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum WindowEvent {
-    WindowResized,
-    // WindowGainedFocus,
-    // WindowLostFocus,
+    /// the terminal window was resized
+    /// carries the new width (cols) and height (rows) of the window
+    WindowResized { cols: u16, rows: u16 },
+    /// the terminal window got the focus back
+    /// requires focus reporting, see focus::enable_focus_reporting
+    WindowGainedFocus,
+    /// the terminal window lost the focus, e.g., another window was clicked
+    /// requires focus reporting, see focus::enable_focus_reporting
+    WindowLostFocus,
+    /// the program was continued after a suspension, e.g., by fg after ctrl+z
+    /// the session modes are applied again, but nothing redraws the screen on its own;
+    /// call ComponentTree::redraw on it
+    /// requires job control, see SessionMeta::job_control
+    Resumed,
     // WindowMaximized,
     // WindowMinimized,
     // WindowClosed,
     // WindowFullscreened,
     // WindowWindowed,
 }
-
-pub struct WindowResized;
-
-use crate::components::Term;
-
-// resolve linux display/compositor protocol
-// returns 0 for wayland or 1 for x11
-#[cfg(target_os = "linux")]
-fn compositor() -> u8 {
-    let is_wayland = std::process::Command::new("printenv")
-        .arg("WAYLAND_DISPLAY")
-        .output();
-    // if err then no wayland
-    if is_wayland.is_err() {
-        1
-    } else {
-        0
-    }
-}
-
-fn window_resized() /* -> WindowResized */
-{
-    #[cfg(target_os = "linux")]
-    match compositor() {
-        0 => {
-            // wayland
-        }
-        1 => {
-            // x11
-        }
-        _ => unreachable!(),
-    }
-
-    #[cfg(target_os = "windows")]
-    // windows get window resize values
-    #[cfg(target_os = "macos")]
-    // macos get window resize values
-    if cfg!(x86_64) {
-        // amd64 macos
-    } else if cfg!(aarch64) {
-        // apple silicon aarch64 macos
-    }
-}
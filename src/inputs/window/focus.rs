@@ -0,0 +1,45 @@
+use std::io::Write;
+
+use super::WindowEvent;
+
+/// enables focus reporting
+/// the terminal then sends ESC[I when its window gains the focus and ESC[O when it loses it,
+/// which inputs::event decodes into WindowEvent::WindowGainedFocus and WindowEvent::WindowLostFocus
+pub fn enable_focus_reporting(writer: &mut impl Write) {
+    _ = writer.write(b"\x1b[?1004h");
+}
+
+/// disables focus reporting
+pub fn disable_focus_reporting(writer: &mut impl Write) {
+    _ = writer.write(b"\x1b[?1004l");
+}
+
+/// decodes a focus report; ESC[I or ESC[O
+/// returns None if the bytes are not exactly one of those
+pub fn decode_focus(bytes: &[u8]) -> Option<WindowEvent> {
+    match bytes {
+        b"\x1b[I" => Some(WindowEvent::WindowGainedFocus),
+        b"\x1b[O" => Some(WindowEvent::WindowLostFocus),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test_focus {
+    use super::decode_focus;
+    use crate::inputs::window::WindowEvent;
+
+    #[test]
+    fn focus() {
+        assert!(matches!(
+            decode_focus(b"\x1b[I"),
+            Some(WindowEvent::WindowGainedFocus)
+        ));
+        assert!(matches!(
+            decode_focus(b"\x1b[O"),
+            Some(WindowEvent::WindowLostFocus)
+        ));
+        assert!(decode_focus(b"\x1b[1;5I").is_none());
+        assert!(decode_focus(b"\x1bOI").is_none());
+    }
+}
@@ -1 +1,32 @@
+use crate::console::signals::{self, SIGWINCH};
+use crate::console::winsize::winsize;
 
+use super::WindowEvent;
+
+/// starts listening to the SIGWINCH signal
+///
+/// once this is called, a blocking inputs::read wakes up as soon as the terminal window gets
+/// resized, and inputs::event then returns a WindowEvent::WindowResized
+pub fn listen() {
+    signals::listen(SIGWINCH);
+}
+
+/// stops listening to the SIGWINCH signal
+pub fn unlisten() {
+    signals::unlisten(SIGWINCH);
+}
+
+// returns the resize event if a SIGWINCH was received since the last call
+// the winsize gets updated with the new window dimensions
+pub(crate) fn take(ws: &mut winsize) -> Option<WindowEvent> {
+    if !signals::take(SIGWINCH) {
+        return None;
+    }
+
+    *ws = winsize::from_ioctl();
+
+    Some(WindowEvent::WindowResized {
+        cols: ws.cols(),
+        rows: ws.rows(),
+    })
+}
@@ -0,0 +1,43 @@
+use crate::console::session;
+use crate::console::signals::{self, SIGCONT, SIGTSTP};
+
+use super::WindowEvent;
+
+/// starts listening to the SIGTSTP and SIGCONT signals
+///
+/// once this is called, a SIGTSTP no longer stops the program right away;
+/// the next inputs::event restores the terminal first, then stops the program.
+/// when it is continued, the session modes get applied again and a WindowEvent::Resumed is returned;
+/// the program redraws its screen on that event, e.g., with ComponentTree::redraw
+///
+/// SessionMeta::job_control calls this on session start
+pub fn listen() {
+    signals::listen(SIGTSTP);
+    signals::listen(SIGCONT);
+}
+
+/// stops listening to the SIGTSTP and SIGCONT signals
+pub fn unlisten() {
+    signals::unlisten(SIGTSTP);
+    signals::unlisten(SIGCONT);
+}
+
+// suspends the program if a SIGTSTP was received since the last call
+// returns the resume event once the program is continued, or after a SIGCONT alone
+pub(crate) fn take() -> Option<WindowEvent> {
+    if signals::take(SIGTSTP) {
+        session::suspend();
+        // the continue signal that woke the program up is this same resume
+        signals::take(SIGCONT);
+
+        return Some(WindowEvent::Resumed);
+    }
+
+    if !signals::take(SIGCONT) {
+        return None;
+    }
+
+    session::resume();
+
+    Some(WindowEvent::Resumed)
+}
@@ -1,5 +1,5 @@
 use std::collections::HashMap;
-use std::io::{StdoutLock, Write};
+use std::io::Write;
 use std::str::Chars;
 
 use crate::components::*;
@@ -10,13 +10,13 @@ use super::spread_padding;
 
 impl Container {
     /// wrapper around the render_border and render_value method calls
-    pub fn render(&self, writer: &mut StdoutLock) {
+    pub fn render(&self, writer: &mut impl Write) {
         self.render_border(writer);
         self.render_value(writer);
     }
 
     /// renders only the items inside the container
-    pub fn render_value(&self, writer: &mut StdoutLock) {
+    pub fn render_value(&self, writer: &mut impl Write) {
         let [_, pol, pot, _, _, pil, pit, _] = spread_padding(&self.padding);
         let cb = if let Border::None = self.border { 0 } else { 1 };
 
@@ -34,7 +34,7 @@ impl Container {
     }
 
     /// renders only the container border
-    pub fn render_border(&self, writer: &mut StdoutLock) {
+    pub fn render_border(&self, writer: &mut impl Write) {
         let [_, pol, pot, _, pir, pil, pit, pib] = spread_padding(&self.padding);
         let [xb, yb] = [self.x0 + pol + 1, self.y0 + pot];
         let mut s = format!("{}\x1b[{};{}f", &self.bstyle, yb, xb);
@@ -66,7 +66,7 @@ impl Container {
 
             s.push_str("\x1b[0m");
 
-            writer.write(s.as_bytes());
+            _ = writer.write_all(s.as_bytes());
         }
     }
 
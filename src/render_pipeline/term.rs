@@ -3,6 +3,9 @@ use std::io::{StdoutLock, Write};
 use std::str::Chars;
 
 use crate::components::*;
+use crate::console::cursor::CursorStyle;
+use crate::console::session::mark_cursor;
+use crate::console::write_synchronized;
 use crate::space::{border::Border, padding::Padding};
 use crate::themes::Style;
 
@@ -10,9 +13,11 @@ use super::spread_padding;
 
 impl Term {
     /// renders the cursor in the self cx, cy position
+    /// with the shape and visibility given by cursor_style
     pub fn render_cursor(&self, writer: &mut StdoutLock) {
-        let pos = format!("\x1b[{};{}f", self.cy, self.cx);
-        _ = writer.write(pos.as_bytes());
+        let pos = format!("\x1b[{};{}f{}", self.cy, self.cx, self.cursor_style().sequence());
+        _ = writer.write_all(pos.as_bytes());
+        mark_cursor(self.cursor_style() != CursorStyle::default());
     }
 
     /// renders only the text objects that have seen some value/border change since the last event
@@ -45,36 +50,39 @@ impl Term {
         vr: Property,
         ar: Property,
     ) {
+        let mut frame = vec![];
         self.containers.iter().for_each(|c| {
             if let Some(val) = c.properties.get(key) {
                 match val {
                     br => {
-                        c.render_border(writer);
+                        c.render_border(&mut frame);
                         c.items.iter().for_each(|t| {
                             if let Some(val) = t.properties.get(key) {
                                 match val {
-                                    br => t.render_border(writer),
-                                    vr => t.render_value(writer),
-                                    ar => t.render(writer),
+                                    br => t.render_border(&mut frame),
+                                    vr => t.render_value(&mut frame),
+                                    ar => t.render(&mut frame),
                                 }
                             }
                         });
                     }
-                    vr => c.render_value(writer),
-                    ar => c.render(writer),
+                    vr => c.render_value(&mut frame),
+                    ar => c.render(&mut frame),
                 }
             } else {
                 c.items.iter().for_each(|t| {
                     if let Some(val) = t.properties.get(key) {
                         match val {
-                            br => t.render_border(writer),
-                            vr => t.render_value(writer),
-                            ar => t.render(writer),
+                            br => t.render_border(&mut frame),
+                            vr => t.render_value(&mut frame),
+                            ar => t.render(&mut frame),
                         }
                     }
                 });
             }
-        })
+        });
+
+        self.write_frame(writer, &frame);
     }
 
     // TODO: add components fields: value_dirty and border_dirty
@@ -83,17 +91,20 @@ impl Term {
     // NOTE: this method renders the entire component; both border and value
     // since it has no way of telling which part to render
     pub fn attribute_render(&self, writer: &mut StdoutLock, attr: &str) {
+        let mut frame = vec![];
         self.containers.iter().for_each(|c| {
             if c.attributes.contains(attr) {
-                c.render(writer);
+                c.render(&mut frame);
             } else {
                 c.items.iter().for_each(|t| {
                     if t.attributes.contains(attr) {
-                        t.render(writer);
+                        t.render(&mut frame);
                     }
                 });
             }
         });
+
+        self.write_frame(writer, &frame);
     }
 
     pub fn partial_render(&self, writer: &mut StdoutLock) {}
@@ -136,6 +147,59 @@ impl Term {
         lines
     }
 
+    // maps every cell of the term buffer to the link covering it, with the link id
+    // a text's value cell x, y sits at ax0 + x, ay0 + y, where the screen starts at 1, 1
+    // computed in usize, a link can reach past the u16 range of a big text
+    fn links(&self) -> Vec<Option<(String, &Hyperlink)>> {
+        let [w, h] = [self.w as usize, self.h as usize];
+        let mut links = vec![None; w * h];
+
+        self.containers.iter().flat_map(|c| c.items.iter()).for_each(|t| {
+            let tw = t.w as usize;
+            for (l, link) in t.links.iter().enumerate() {
+                let id = link.id.clone().unwrap_or_else(|| t.link_id(l));
+                for cell in link.start..link.end.min(tw * t.h as usize) {
+                    let [x, y] = [cell % tw, cell / tw];
+                    let [ax, ay] = [
+                        (t.ax0 as usize + x).saturating_sub(1),
+                        (t.ay0 as usize + y).saturating_sub(1),
+                    ];
+                    if ax < w && ay < h {
+                        links[ax + ay * w] = Some((id.clone(), link));
+                    }
+                }
+            }
+        });
+
+        links
+    }
+
+    // maps every cell of the term buffer to the grapheme cluster of a text value cell, with its width
+    // laid out the same way as the links
+    fn graphemes(&self) -> Vec<Option<(&str, usize)>> {
+        let [w, h] = [self.w as usize, self.h as usize];
+        let mut graphemes = vec![None; w * h];
+
+        self.containers.iter().flat_map(|c| c.items.iter()).for_each(|t| {
+            let tw = t.w as usize;
+            for cell in t.graphemes.keys() {
+                let Some(grapheme) = t.grapheme_at(*cell) else {
+                    continue;
+                };
+                let [x, y] = [cell % tw, cell / tw];
+                let [ax, ay] = [
+                    (t.ax0 as usize + x).saturating_sub(1),
+                    (t.ay0 as usize + y).saturating_sub(1),
+                ];
+                if ax < w && ay < h {
+                    graphemes[ax + ay * w] = Some(grapheme);
+                }
+            }
+        });
+
+        graphemes
+    }
+
     /// renders the whole buffer into the terminal
     /// assumes that Term.clear() has been used before hand to prepare the terminal display for the
     /// rendering
@@ -144,15 +208,45 @@ impl Term {
     // need a way to map whatever style to some range of positions in the term buffer
     // that way, atomic style implementation becomes easy to call from anywhere
     pub fn render(&mut self, writer: &mut StdoutLock) {
+        let frame = self.frame();
+        self.write_frame(writer, frame.as_bytes());
+        mark_cursor(self.cursor_style() != CursorStyle::default());
+    }
+
+    // the whole buffer as a single frame, ending with the cursor position and style
+    pub(crate) fn frame(&mut self) -> String {
         let cells = self.prepare();
+        let links = self.links();
+        let graphemes = self.graphemes();
 
         let mut s = String::new();
 
         let mut line = 0;
         let mut idx = 0;
+        // the link of the previous cell
+        let mut open: Option<&(String, &Hyperlink)> = None;
+        // the cell covered by the wide cluster before it
+        let mut covered = false;
+
+        cells.iter().enumerate().for_each(|(cell, c)| {
+            let link = links[cell].as_ref();
+            if link.map(|(id, _)| id) != open.map(|(id, _)| id) {
+                if open.is_some() {
+                    s.push_str(Hyperlink::CLOSE);
+                }
+                if let Some((id, link)) = link {
+                    s.push_str(&link.open(id));
+                }
+                open = link;
+            }
 
-        cells.iter().for_each(|c| {
-            if let Some(ch) = c {
+            // a cluster is drawn where its first char landed
+            let grapheme = graphemes[cell].filter(|(g, _)| c.is_some_and(|ch| g.starts_with(ch)));
+            if std::mem::take(&mut covered) {
+            } else if let Some((g, cols)) = grapheme {
+                s.push_str(g);
+                covered = cols == 2;
+            } else if let Some(ch) = c {
                 // print!("found char, ");
                 s.push(*ch);
             } else {
@@ -161,9 +255,13 @@ impl Term {
             }
             idx += 1;
             if idx == self.w {
+                // links get closed before the line break and opened again on the next line
+                if open.take().is_some() {
+                    s.push_str(Hyperlink::CLOSE);
+                }
                 idx = 0;
                 line += 1;
-                if line < self.h - 1 {
+                if line < self.h {
                     // println!("breaking line at {{{}}}", &s[s.len() - 1..s.len()]);
                     s.push_str("\r\n");
                 }
@@ -174,15 +272,28 @@ impl Term {
 
         let pos = format!("\x1b[{};{}f", self.cy, self.cx);
         s.push_str(&pos);
+        s.push_str(&self.cursor_style().sequence());
         // println!("{}", s);
-        _ = writer.write(s.as_bytes());
+        s
+    }
+
+    /// writes a whole frame to the terminal in a single write
+    /// wrapped in a synchronized update when synchronized_output is on,
+    /// so that the terminal shows the frame at once instead of tearing it
+    pub fn write_frame(&self, writer: &mut StdoutLock, frame: &[u8]) {
+        match self.synchronized_output {
+            true => write_synchronized(writer, frame),
+            false => {
+                _ = writer.write_all(frame);
+            }
+        }
         _ = writer.flush();
     }
 
     /// clears the whole terminal display
     /// first implementation of clear
     pub fn clear(&self, writer: &mut StdoutLock) {
-        writer.write(b"\x1b[H\x1b[J");
+        self.write_frame(writer, b"\x1b[H\x1b[J");
     }
 
     /// clears the whole terminal display
@@ -193,6 +304,6 @@ impl Term {
             .into_iter()
             .for_each(|_| s.push_str("\x1b[2K\x1b[C"));
         s.push_str("\x1b[H");
-        writer.write(s.as_bytes());
+        self.write_frame(writer, s.as_bytes());
     }
 }
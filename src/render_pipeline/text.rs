@@ -1,5 +1,5 @@
 use std::collections::HashMap;
-use std::io::{StdoutLock, Write};
+use std::io::Write;
 use std::str::Chars;
 
 use crate::components::*;
@@ -10,13 +10,13 @@ use super::spread_padding;
 
 impl Text {
     /// wrapper around the render_border and render_value method calls
-    pub fn render(&self, writer: &mut StdoutLock) {
+    pub fn render(&self, writer: &mut impl Write) {
         self.render_border(writer);
         self.render_value(writer);
     }
 
     /// renders only the text border
-    pub fn render_border(&self, writer: &mut StdoutLock) {
+    pub fn render_border(&self, writer: &mut impl Write) {
         let [por, pol, pot, pob, pir, pil, pit, pib] = spread_padding(&self.padding);
         let [xb, yb] = [self.ax0 - pil - 1, self.ay0 - pit - 1];
         let mut s = format!("{}\x1b[{};{}f", &self.bstyle, yb, xb);
@@ -48,12 +48,12 @@ impl Text {
 
             s.push_str("\x1b[0m");
 
-            writer.write(s.as_bytes());
+            _ = writer.write_all(s.as_bytes());
         }
     }
 
     /// renders only the text value
-    pub fn render_value(&self, writer: &mut StdoutLock) {
+    pub fn render_value(&self, writer: &mut impl Write) {
         let h0 = self.ay0;
 
         let del = |s: &mut String, y: u16| {
@@ -62,14 +62,43 @@ impl Text {
 
         let put = |s: &mut String, y: u16| {
             *s += &format!("\x1b[{};{}f", h0 + y, self.ax0);
+            // the link of the previous cell
+            let mut open = None;
+            // the cell covered by the wide cluster before it
+            let mut covered = false;
             for idx in 0..self.w {
-                let c = self.value[(idx + y * self.w) as usize];
+                let cell = (idx + y * self.w) as usize;
+                let link = self.link_at(cell);
+                if link.map(|(l, _)| l) != open {
+                    if open.is_some() {
+                        s.push_str(Hyperlink::CLOSE);
+                    }
+                    if let Some((l, link)) = link {
+                        s.push_str(&link.open(&self.link_id(l)));
+                    }
+                    open = link.map(|(l, _)| l);
+                }
+
+                if std::mem::take(&mut covered) {
+                    continue;
+                }
+                if let Some((cluster, cols)) = self.grapheme_at(cell) {
+                    s.push_str(cluster);
+                    covered = cols == 2;
+                    continue;
+                }
+
+                let c = self.value[cell];
                 if c.is_some() {
                     s.push(c.unwrap());
                 } else {
                     s.push_str("\x1b[C")
                 };
             }
+            // links are closed at every line end and opened again on the next line with the same id
+            if open.is_some() {
+                s.push_str(Hyperlink::CLOSE);
+            }
         };
 
         let mut s = format!("{}", &self.vstyle);
@@ -82,7 +111,7 @@ impl Text {
 
         s += "\x1b[0m";
 
-        writer.write(s.as_bytes());
+        _ = writer.write_all(s.as_bytes());
     }
 
     // returns the text component's width and height + the component borders and paddings